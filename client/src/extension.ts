@@ -5,18 +5,10 @@
 
 import * as path from "path";
 import {
-  languages,
   workspace,
-  EventEmitter,
   ExtensionContext,
   window,
-  InlayHintsProvider,
-  TextDocument,
-  CancellationToken,
   Range,
-  InlayHint,
-  TextDocumentChangeEvent,
-  ProviderResult,
   commands,
   WorkspaceEdit,
   TextEdit,
@@ -25,7 +17,6 @@ import {
 } from "vscode";
 
 import {
-  Disposable,
   Executable,
   LanguageClient,
   LanguageClientOptions,
@@ -84,7 +75,6 @@ export async function activate(context: ExtensionContext) {
 
   // Create the language client and start the client.
   client = new LanguageClient("nano_crl2_lsp", "nanoCRL2-lsp", serverOptions, clientOptions);
-  // activateInlayHints(context);
   client.start();
 }
 
@@ -94,81 +84,3 @@ export function deactivate(): Thenable<void> | undefined {
   }
   return client.stop();
 }
-
-export function activateInlayHints(ctx: ExtensionContext) {
-  const maybeUpdater = {
-    hintsProvider: null as Disposable | null,
-    updateHintsEventEmitter: new EventEmitter<void>(),
-
-    async onConfigChange() {
-      this.dispose();
-
-      const event = this.updateHintsEventEmitter.event;
-      // this.hintsProvider = languages.registerInlayHintsProvider(
-      //   { scheme: "file", language: "mcrl2" },
-      //   // new (class implements InlayHintsProvider {
-      //   //   onDidChangeInlayHints = event;
-      //   //   resolveInlayHint(hint: InlayHint, token: CancellationToken): ProviderResult<InlayHint> {
-      //   //     const ret = {
-      //   //       label: hint.label,
-      //   //       ...hint,
-      //   //     };
-      //   //     return ret;
-      //   //   }
-      //   //   async provideInlayHints(
-      //   //     document: TextDocument,
-      //   //     range: Range,
-      //   //     token: CancellationToken
-      //   //   ): Promise<InlayHint[]> {
-      //   //     const hints = (await client
-      //   //       .sendRequest("custom/inlay_hint", { path: document.uri.toString() })
-      //   //       .catch(err => null)) as [number, number, string][];
-      //   //     if (hints == null) {
-      //   //       return [];
-      //   //     } else {
-      //   //       return hints.map(item => {
-      //   //         const [start, end, label] = item;
-      //   //         let startPosition = document.positionAt(start);
-      //   //         let endPosition = document.positionAt(end);
-      //   //         return {
-      //   //           position: endPosition,
-      //   //           paddingLeft: true,
-      //   //           label: [
-      //   //             {
-      //   //               value: `${label}`,
-      //   //               // location: {
-      //   //               //   uri: document.uri,
-      //   //               //   range: new Range(1, 0, 1, 0)
-      //   //               // }
-      //   //               command: {
-      //   //                 title: "hello world",
-      //   //                 command: "helloworld.helloWorld",
-      //   //                 arguments: [document.uri],
-      //   //               },
-      //   //             },
-      //   //           ],
-      //   //         };
-      //   //       });
-      //   //     }
-      //   //   }
-      //   // })()
-      // );
-    },
-
-    onDidChangeTextDocument({ contentChanges, document }: TextDocumentChangeEvent) {
-      // debugger
-      // this.updateHintsEventEmitter.fire();
-    },
-
-    dispose() {
-      this.hintsProvider?.dispose();
-      this.hintsProvider = null;
-      this.updateHintsEventEmitter.dispose();
-    },
-  };
-
-  workspace.onDidChangeConfiguration(maybeUpdater.onConfigChange, maybeUpdater, ctx.subscriptions);
-  workspace.onDidChangeTextDocument(maybeUpdater.onDidChangeTextDocument, maybeUpdater, ctx.subscriptions);
-
-  maybeUpdater.onConfigChange().catch(console.error);
-}
@@ -0,0 +1,476 @@
+use nano_crl2::core::lexer::LexicalElement;
+
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails,
+    Documentation, MarkupContent, MarkupKind,
+};
+
+/// A description of a sort, operator or function from the standard data
+/// library of mCRL2.
+pub struct BuiltinItem {
+    /// The name of the item as it is written in a model, e.g. `Nat` or `++`.
+    pub name: &'static str,
+    pub kind: BuiltinKind,
+    /// One or more signatures, one per line if the item is overloaded.
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BuiltinKind {
+    Sort,
+    Operator,
+    Function,
+}
+
+pub const BUILTIN_ITEMS: &[BuiltinItem] = &[
+    // sorts
+    BuiltinItem {
+        name: "Bool",
+        kind: BuiltinKind::Sort,
+        signature: "sort Bool",
+        description: "The booleans, with constants `true` and `false`.",
+    },
+    BuiltinItem {
+        name: "Pos",
+        kind: BuiltinKind::Sort,
+        signature: "sort Pos",
+        description: "The positive natural numbers `1, 2, 3, ...`.",
+    },
+    BuiltinItem {
+        name: "Nat",
+        kind: BuiltinKind::Sort,
+        signature: "sort Nat",
+        description: "The natural numbers `0, 1, 2, ...`. `Pos` is a subsort of `Nat`.",
+    },
+    BuiltinItem {
+        name: "Int",
+        kind: BuiltinKind::Sort,
+        signature: "sort Int",
+        description: "The integers. `Nat` is a subsort of `Int`.",
+    },
+    BuiltinItem {
+        name: "Real",
+        kind: BuiltinKind::Sort,
+        signature: "sort Real",
+        description: "The rational numbers. `Int` is a subsort of `Real`.",
+    },
+    BuiltinItem {
+        name: "List",
+        kind: BuiltinKind::Sort,
+        signature: "sort List(S)",
+        description: "Finite lists with elements of sort `S`, such as `[]` and `[1, 2, 3]`.",
+    },
+    BuiltinItem {
+        name: "Set",
+        kind: BuiltinKind::Sort,
+        signature: "sort Set(S)",
+        description: "Possibly infinite sets with elements of sort `S`, such as \
+            `{ x: Nat | x < 10 }`.",
+    },
+    BuiltinItem {
+        name: "FSet",
+        kind: BuiltinKind::Sort,
+        signature: "sort FSet(S)",
+        description: "Finite sets with elements of sort `S`, such as `{}` and `{1, 2}`.",
+    },
+    BuiltinItem {
+        name: "Bag",
+        kind: BuiltinKind::Sort,
+        signature: "sort Bag(S)",
+        description: "Possibly infinite multisets with elements of sort `S`, such as \
+            `{ x: Nat | 2 }`.",
+    },
+    BuiltinItem {
+        name: "FBag",
+        kind: BuiltinKind::Sort,
+        signature: "sort FBag(S)",
+        description: "Finite multisets with elements of sort `S`, such as `{1: 2, 3: 1}`.",
+    },
+    // operators
+    BuiltinItem {
+        name: "#",
+        kind: BuiltinKind::Operator,
+        signature: "S1 # S2 -> S\n# : List(S) -> Nat",
+        description: "In a sort, separates the parameter sorts of a function sort. \
+            As a prefix operator on data, returns the length of a list.",
+    },
+    BuiltinItem {
+        name: "++",
+        kind: BuiltinKind::Operator,
+        signature: "++ : List(S) # List(S) -> List(S)",
+        description: "Concatenates two lists.",
+    },
+    BuiltinItem {
+        name: "|>",
+        kind: BuiltinKind::Operator,
+        signature: "|> : S # List(S) -> List(S)",
+        description: "Prepends an element to a list (cons), e.g. `1 |> [2, 3] == [1, 2, 3]`.",
+    },
+    BuiltinItem {
+        name: "<|",
+        kind: BuiltinKind::Operator,
+        signature: "<| : List(S) # S -> List(S)",
+        description: "Appends an element to a list (snoc), e.g. `[1, 2] <| 3 == [1, 2, 3]`.",
+    },
+    BuiltinItem {
+        name: "in",
+        kind: BuiltinKind::Operator,
+        signature: "in : S # List(S) -> Bool\nin : S # Set(S) -> Bool\nin : S # FSet(S) -> Bool\n\
+            in : S # Bag(S) -> Bool\nin : S # FBag(S) -> Bool",
+        description: "Returns whether an element is contained in a list, set or bag.",
+    },
+    BuiltinItem {
+        name: "div",
+        kind: BuiltinKind::Operator,
+        signature: "div : Nat # Pos -> Nat\ndiv : Int # Pos -> Int",
+        description: "Integer division, rounding towards negative infinity.",
+    },
+    BuiltinItem {
+        name: "mod",
+        kind: BuiltinKind::Operator,
+        signature: "mod : Nat # Pos -> Nat\nmod : Int # Pos -> Nat",
+        description: "The remainder of integer division, which is never negative.",
+    },
+    BuiltinItem {
+        name: ".",
+        kind: BuiltinKind::Operator,
+        signature: ". : List(S) # Nat -> S",
+        description: "In a data expression, returns the element of a list at the given \
+            (zero-based) index. In a process expression, denotes sequential composition.",
+    },
+    BuiltinItem {
+        name: "+",
+        kind: BuiltinKind::Operator,
+        signature: "+ : Nat # Nat -> Nat\n+ : Int # Int -> Int\n+ : Real # Real -> Real\n\
+            + : Set(S) # Set(S) -> Set(S)",
+        description: "In a data expression, adds two numbers or takes the union of two \
+            sets or bags. In a process expression, denotes non-deterministic choice.",
+    },
+    BuiltinItem {
+        name: "-",
+        kind: BuiltinKind::Operator,
+        signature: "- : Int # Int -> Int\n- : Real # Real -> Real\n- : Set(S) # Set(S) -> Set(S)",
+        description: "Subtracts two numbers or takes the difference of two sets or bags. \
+            As a prefix operator, negates a number.",
+    },
+    BuiltinItem {
+        name: "*",
+        kind: BuiltinKind::Operator,
+        signature: "* : Nat # Nat -> Nat\n* : Int # Int -> Int\n* : Real # Real -> Real\n\
+            * : Set(S) # Set(S) -> Set(S)",
+        description: "Multiplies two numbers or takes the intersection of two sets or bags.",
+    },
+    BuiltinItem {
+        name: "/",
+        kind: BuiltinKind::Operator,
+        signature: "/ : Real # Real -> Real",
+        description: "Divides two numbers, resulting in a `Real`.",
+    },
+    BuiltinItem {
+        name: "==",
+        kind: BuiltinKind::Operator,
+        signature: "== : S # S -> Bool",
+        description: "Returns whether two values of the same sort are equal.",
+    },
+    BuiltinItem {
+        name: "!=",
+        kind: BuiltinKind::Operator,
+        signature: "!= : S # S -> Bool",
+        description: "Returns whether two values of the same sort are not equal.",
+    },
+    BuiltinItem {
+        name: "<",
+        kind: BuiltinKind::Operator,
+        signature: "< : Nat # Nat -> Bool\n< : Int # Int -> Bool\n< : Real # Real -> Bool\n\
+            < : Set(S) # Set(S) -> Bool",
+        description: "Returns whether the first number is less than the second. \
+            On sets and bags, returns whether the first is a proper subset of the second.",
+    },
+    BuiltinItem {
+        name: "<=",
+        kind: BuiltinKind::Operator,
+        signature: "<= : Nat # Nat -> Bool\n<= : Int # Int -> Bool\n<= : Real # Real -> Bool\n\
+            <= : Set(S) # Set(S) -> Bool",
+        description: "Returns whether the first number is at most the second. \
+            On sets and bags, returns whether the first is a subset of the second.",
+    },
+    BuiltinItem {
+        name: ">",
+        kind: BuiltinKind::Operator,
+        signature: "> : Nat # Nat -> Bool\n> : Int # Int -> Bool\n> : Real # Real -> Bool\n\
+            > : Set(S) # Set(S) -> Bool",
+        description: "Returns whether the first number is greater than the second. \
+            On sets and bags, returns whether the first is a proper superset of the second.",
+    },
+    BuiltinItem {
+        name: ">=",
+        kind: BuiltinKind::Operator,
+        signature: ">= : Nat # Nat -> Bool\n>= : Int # Int -> Bool\n>= : Real # Real -> Bool\n\
+            >= : Set(S) # Set(S) -> Bool",
+        description: "Returns whether the first number is at least the second. \
+            On sets and bags, returns whether the first is a superset of the second.",
+    },
+    BuiltinItem {
+        name: "!",
+        kind: BuiltinKind::Operator,
+        signature: "! : Bool -> Bool\n! : Set(S) -> Set(S)",
+        description: "Negates a boolean, or takes the complement of a set.",
+    },
+    BuiltinItem {
+        name: "&&",
+        kind: BuiltinKind::Operator,
+        signature: "&& : Bool # Bool -> Bool",
+        description: "Logical conjunction.",
+    },
+    BuiltinItem {
+        name: "||",
+        kind: BuiltinKind::Operator,
+        signature: "|| : Bool # Bool -> Bool",
+        description: "In a data expression, denotes logical disjunction. In a process \
+            expression, denotes parallel composition.",
+    },
+    BuiltinItem {
+        name: "=>",
+        kind: BuiltinKind::Operator,
+        signature: "=> : Bool # Bool -> Bool",
+        description: "Logical implication.",
+    },
+    // functions
+    BuiltinItem {
+        name: "head",
+        kind: BuiltinKind::Function,
+        signature: "head : List(S) -> S",
+        description: "Returns the first element of a non-empty list.",
+    },
+    BuiltinItem {
+        name: "tail",
+        kind: BuiltinKind::Function,
+        signature: "tail : List(S) -> List(S)",
+        description: "Returns a non-empty list without its first element.",
+    },
+    BuiltinItem {
+        name: "rhead",
+        kind: BuiltinKind::Function,
+        signature: "rhead : List(S) -> S",
+        description: "Returns the last element of a non-empty list.",
+    },
+    BuiltinItem {
+        name: "rtail",
+        kind: BuiltinKind::Function,
+        signature: "rtail : List(S) -> List(S)",
+        description: "Returns a non-empty list without its last element.",
+    },
+    BuiltinItem {
+        name: "count",
+        kind: BuiltinKind::Function,
+        signature: "count : S # Bag(S) -> Nat\ncount : S # FBag(S) -> Nat",
+        description: "Returns the number of times that an element occurs in a bag.",
+    },
+    BuiltinItem {
+        name: "if",
+        kind: BuiltinKind::Function,
+        signature: "if : Bool # S # S -> S",
+        description: "Returns the second argument if the condition holds, and the third \
+            argument otherwise.",
+    },
+    BuiltinItem {
+        name: "succ",
+        kind: BuiltinKind::Function,
+        signature: "succ : Nat -> Pos\nsucc : Int -> Int",
+        description: "Returns the successor of a number.",
+    },
+    BuiltinItem {
+        name: "pred",
+        kind: BuiltinKind::Function,
+        signature: "pred : Pos -> Nat\npred : Nat -> Int\npred : Int -> Int",
+        description: "Returns the predecessor of a number.",
+    },
+    BuiltinItem {
+        name: "max",
+        kind: BuiltinKind::Function,
+        signature: "max : Nat # Nat -> Nat\nmax : Int # Int -> Int\nmax : Real # Real -> Real",
+        description: "Returns the largest of two numbers.",
+    },
+    BuiltinItem {
+        name: "min",
+        kind: BuiltinKind::Function,
+        signature: "min : Nat # Nat -> Nat\nmin : Int # Int -> Int\nmin : Real # Real -> Real",
+        description: "Returns the smallest of two numbers.",
+    },
+    BuiltinItem {
+        name: "abs",
+        kind: BuiltinKind::Function,
+        signature: "abs : Int -> Nat\nabs : Real -> Real",
+        description: "Returns the absolute value of a number.",
+    },
+    BuiltinItem {
+        name: "exp",
+        kind: BuiltinKind::Function,
+        signature: "exp : Nat # Nat -> Nat\nexp : Int # Nat -> Int\nexp : Real # Int -> Real",
+        description: "Raises a number to the given power.",
+    },
+    BuiltinItem {
+        name: "floor",
+        kind: BuiltinKind::Function,
+        signature: "floor : Real -> Int",
+        description: "Rounds a real number towards negative infinity.",
+    },
+    BuiltinItem {
+        name: "ceil",
+        kind: BuiltinKind::Function,
+        signature: "ceil : Real -> Int",
+        description: "Rounds a real number towards positive infinity.",
+    },
+    BuiltinItem {
+        name: "round",
+        kind: BuiltinKind::Function,
+        signature: "round : Real -> Int",
+        description: "Rounds a real number to the nearest integer.",
+    },
+    BuiltinItem {
+        name: "Pos2Nat",
+        kind: BuiltinKind::Function,
+        signature: "Pos2Nat : Pos -> Nat",
+        description: "Converts a positive number to a natural number.",
+    },
+    BuiltinItem {
+        name: "Nat2Pos",
+        kind: BuiltinKind::Function,
+        signature: "Nat2Pos : Nat -> Pos",
+        description: "Converts a natural number to a positive number. The result is \
+            unspecified for `0`.",
+    },
+    BuiltinItem {
+        name: "Nat2Int",
+        kind: BuiltinKind::Function,
+        signature: "Nat2Int : Nat -> Int",
+        description: "Converts a natural number to an integer.",
+    },
+    BuiltinItem {
+        name: "Int2Nat",
+        kind: BuiltinKind::Function,
+        signature: "Int2Nat : Int -> Nat",
+        description: "Converts an integer to a natural number. The result is unspecified \
+            for negative numbers.",
+    },
+    BuiltinItem {
+        name: "Int2Pos",
+        kind: BuiltinKind::Function,
+        signature: "Int2Pos : Int -> Pos",
+        description: "Converts an integer to a positive number. The result is unspecified \
+            for numbers smaller than `1`.",
+    },
+    BuiltinItem {
+        name: "Int2Real",
+        kind: BuiltinKind::Function,
+        signature: "Int2Real : Int -> Real",
+        description: "Converts an integer to a real number.",
+    },
+    BuiltinItem {
+        name: "Real2Int",
+        kind: BuiltinKind::Function,
+        signature: "Real2Int : Real -> Int",
+        description: "Converts a real number to an integer. The result is unspecified if \
+            the number is not an integer.",
+    },
+    BuiltinItem {
+        name: "Set2Bag",
+        kind: BuiltinKind::Function,
+        signature: "Set2Bag : Set(S) -> Bag(S)",
+        description: "Converts a set to a bag in which every element occurs once.",
+    },
+    BuiltinItem {
+        name: "Bag2Set",
+        kind: BuiltinKind::Function,
+        signature: "Bag2Set : Bag(S) -> Set(S)",
+        description: "Converts a bag to the set of elements that occur in it.",
+    },
+];
+
+/// Returns the built-in item with the given name, or `None` if there is none.
+pub fn get_builtin_item(name: &str) -> Option<&'static BuiltinItem> {
+    BUILTIN_ITEMS.iter().find(|item| item.name == name)
+}
+
+/// Returns the built-in item that corresponds to a keyword or operator token,
+/// or `None` if there is none.
+///
+/// Identifiers are not handled here, since they first need to be checked
+/// against user definitions; use `get_builtin_item` for those.
+pub fn get_builtin_item_of_lexical_element(
+    value: &LexicalElement,
+) -> Option<&'static BuiltinItem> {
+    use LexicalElement::*;
+
+    let name = match value {
+        Bool => "Bool",
+        Pos => "Pos",
+        Nat => "Nat",
+        Int => "Int",
+        Real => "Real",
+        List => "List",
+        Set => "Set",
+        FSet => "FSet",
+        Bag => "Bag",
+        FBag => "FBag",
+        HashSign => "#",
+        Concat => "++",
+        ConsOperator => "|>",
+        SnocOperator => "<|",
+        In => "in",
+        Div => "div",
+        Mod => "mod",
+        Period => ".",
+        Plus => "+",
+        Dash => "-",
+        Asterisk => "*",
+        Slash => "/",
+        DoubleEquals => "==",
+        NotEquals => "!=",
+        LessThan => "<",
+        LessThanEquals => "<=",
+        GreaterThan => ">",
+        GreaterThanEquals => ">=",
+        ExclamationMark => "!",
+        DoubleAmpersand => "&&",
+        DoublePipe => "||",
+        ThickArrow => "=>",
+        If => "if",
+        _ => return None,
+    };
+    get_builtin_item(name)
+}
+
+/// Returns a Markdown description of a built-in item.
+pub fn get_builtin_documentation(item: &BuiltinItem) -> String {
+    format!("```mcrl2\n{}\n```\n\n{}", item.signature, item.description)
+}
+
+/// Returns completion items for the built-in sorts and functions.
+///
+/// Operators are left out, since they are not completed as identifiers.
+pub fn get_builtin_completion_items() -> Vec<CompletionItem> {
+    BUILTIN_ITEMS
+        .iter()
+        .filter(|item| item.kind != BuiltinKind::Operator)
+        .map(|item| CompletionItem {
+            label: item.name.to_owned(),
+            label_details: Some(CompletionItemLabelDetails {
+                detail: None,
+                description: Some("built-in".to_owned()),
+            }),
+            kind: Some(match item.kind {
+                BuiltinKind::Sort => CompletionItemKind::STRUCT,
+                BuiltinKind::Operator => CompletionItemKind::OPERATOR,
+                BuiltinKind::Function => CompletionItemKind::FUNCTION,
+            }),
+            detail: item.signature.lines().next().map(|line| line.to_owned()),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: get_builtin_documentation(item),
+            })),
+            ..Default::default()
+        })
+        .collect()
+}
@@ -3,6 +3,7 @@ pub struct EditorConfig {
     pub editor: Editor,
     pub check_parse_errors_continuously: bool,
     pub check_errors_continuously: bool,
+    pub check_lints_continuously: bool,
 }
 
 #[derive(Eq, Debug, PartialEq)]
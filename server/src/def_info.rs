@@ -6,20 +6,34 @@ use nano_crl2::analysis::semantic::sort_resolution::{
 use nano_crl2::core::syntax::Identifier;
 use nano_crl2::ir::decl::{DefId, IrDecl, IrDeclEnum};
 use nano_crl2::ir::display::ResolvedSortDisplay;
-use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::iterator::{DefiningNode, get_def_data, get_defining_node_from_def};
 use nano_crl2::ir::module::IrModule;
 use nano_crl2::ir::sort::ResolvedSort;
 use nano_crl2::util::caching::Interned;
 
+use serde::{Deserialize, Serialize};
+
 use std::fmt::{Display, Formatter};
 
 use tower_lsp::lsp_types::{
     CompletionItem, CompletionItemKind, CompletionItemLabelDetails,
 };
 
+/// The data that is attached to a completion item, so that its documentation
+/// can be filled in lazily when the client sends a `completionItem/resolve`.
+///
+/// The definition is identified by the location of its identifier.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CompletionItemData {
+    pub file_name: String,
+    pub line: u32,
+    pub character: u32,
+}
+
 pub fn get_completion_item(
     context: &AnalysisContext,
     module: &IrModule,
+    file_name: &str,
     def: DefId,
 ) -> CompletionItem {
     let def_info = get_def_info(context, module, def);
@@ -30,6 +44,13 @@ pub fn get_completion_item(
 
     let detail = DefInfoDisplay::new(module, &def_info).to_string();
 
+    let identifier_loc = get_def_data(module, module.get_def_source(def)).unwrap().2;
+    let data = CompletionItemData {
+        file_name: file_name.to_owned(),
+        line: identifier_loc.get_start_line(),
+        character: identifier_loc.get_start_char(),
+    };
+
     CompletionItem {
         label: def_info.identifier.to_string(),
         label_details: Some(CompletionItemLabelDetails {
@@ -38,7 +59,7 @@ pub fn get_completion_item(
         }),
         kind: Some(def_info.completion_item_kind),
         detail: Some(detail),
-        // TODO documentation: Some(...)
+        data: serde_json::to_value(data).ok(),
         ..Default::default()
     }
 }
@@ -0,0 +1,222 @@
+use crate::lint::LintKind;
+use crate::util::{get_source_slice, range_equals};
+
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+
+use tower_lsp::lsp_types::{CodeDescription, NumberOrString, Url};
+
+/// The location of the catalogue that describes every diagnostic code.
+const CATALOGUE_URL: &str =
+    "https://github.com/emilia-h/nano-crl2-lsp/blob/main/server/docs/diagnostics.md";
+
+/// A kind of diagnostic with a stable code, so that it can be looked up in the
+/// catalogue (see `docs/diagnostics.md`) and suppressed.
+#[derive(Debug, Eq, PartialEq)]
+pub struct DiagnosticKind {
+    /// The stable code, e.g. `E0102` for errors and `W0001` for warnings.
+    pub code: &'static str,
+    /// A short name for this kind, e.g. `unused`.
+    pub name: &'static str,
+}
+
+pub const SYNTAX_ERROR: DiagnosticKind = DiagnosticKind {
+    code: "E0001",
+    name: "syntax-error",
+};
+
+pub const UNRESOLVED_NAME: DiagnosticKind = DiagnosticKind {
+    code: "E0101",
+    name: "unresolved-name",
+};
+
+pub const DUPLICATE_DEFINITION: DiagnosticKind = DiagnosticKind {
+    code: "E0102",
+    name: "duplicate-definition",
+};
+
+pub const SORT_ERROR: DiagnosticKind = DiagnosticKind {
+    code: "E0201",
+    name: "sort-error",
+};
+
+pub const UNGUARDED_RECURSION: DiagnosticKind = DiagnosticKind {
+    code: "E0301",
+    name: "unguarded-recursion",
+};
+
+pub const OTHER_ERROR: DiagnosticKind = DiagnosticKind {
+    code: "E0999",
+    name: "other",
+};
+
+pub const UNUSED: DiagnosticKind = DiagnosticKind {
+    code: "W0001",
+    name: "unused",
+};
+
+pub const SHADOWING: DiagnosticKind = DiagnosticKind {
+    code: "W0002",
+    name: "shadowing",
+};
+
+pub const UNBOUND_REWRITE_VARIABLE: DiagnosticKind = DiagnosticKind {
+    code: "W0003",
+    name: "unbound-rewrite-variable",
+};
+
+pub const CONSTRUCTOR_HEAD: DiagnosticKind = DiagnosticKind {
+    code: "W0004",
+    name: "constructor-head",
+};
+
+pub const DUPLICATE_EQUATION: DiagnosticKind = DiagnosticKind {
+    code: "W0005",
+    name: "duplicate-equation",
+};
+
+pub const NON_EXHAUSTIVE: DiagnosticKind = DiagnosticKind {
+    code: "W0006",
+    name: "non-exhaustive",
+};
+
+pub const OVERLAPPING_EQUATIONS: DiagnosticKind = DiagnosticKind {
+    code: "W0007",
+    name: "overlapping-equations",
+};
+
+pub const INVALID_ACTION_RULE: DiagnosticKind = DiagnosticKind {
+    code: "W0008",
+    name: "invalid-action-rule",
+};
+
+pub const UNPRODUCED_ACTION: DiagnosticKind = DiagnosticKind {
+    code: "W0009",
+    name: "unproduced-action",
+};
+
+pub const IMPOSSIBLE_MULTI_ACTION: DiagnosticKind = DiagnosticKind {
+    code: "W0010",
+    name: "impossible-multi-action",
+};
+
+pub const UNREACHABLE: DiagnosticKind = DiagnosticKind {
+    code: "W0011",
+    name: "unreachable",
+};
+
+pub fn get_lint_diagnostic_kind(kind: LintKind) -> &'static DiagnosticKind {
+    match kind {
+        LintKind::Unused => &UNUSED,
+        LintKind::Shadowing => &SHADOWING,
+        LintKind::UnboundRewriteVariable => &UNBOUND_REWRITE_VARIABLE,
+        LintKind::ConstructorHead => &CONSTRUCTOR_HEAD,
+        LintKind::DuplicateEquation => &DUPLICATE_EQUATION,
+        LintKind::NonExhaustive => &NON_EXHAUSTIVE,
+        LintKind::OverlappingEquations => &OVERLAPPING_EQUATIONS,
+        LintKind::UnguardedRecursion => &UNGUARDED_RECURSION,
+        LintKind::InvalidActionRule => &INVALID_ACTION_RULE,
+        LintKind::UnproducedAction => &UNPRODUCED_ACTION,
+        LintKind::ImpossibleMultiAction => &IMPOSSIBLE_MULTI_ACTION,
+        LintKind::Unreachable => &UNREACHABLE,
+    }
+}
+
+/// Determines the kind of a diagnostic that is reported by nanoCRL2, based on
+/// the component that reported it (e.g. the parser or name resolution).
+///
+/// Name resolution reports both names that cannot be resolved and duplicate
+/// definitions. A diagnostic counts as a duplicate definition if it is
+/// reported at a declaration that has `other_declarations` with the same name
+/// (see `get_other_declarations()`).
+pub fn get_analysis_diagnostic_kind(
+    component: &str,
+    other_declarations: &[SourceRange],
+) -> &'static DiagnosticKind {
+    let component = component.to_lowercase();
+    if component.contains("lex") || component.contains("pars") {
+        &SYNTAX_ERROR
+    } else if component.contains("name") {
+        if other_declarations.is_empty() {
+            &UNRESOLVED_NAME
+        } else {
+            &DUPLICATE_DEFINITION
+        }
+    } else if component.contains("sort") {
+        &SORT_ERROR
+    } else {
+        &OTHER_ERROR
+    }
+}
+
+impl DiagnosticKind {
+    pub fn get_lsp_code(&self) -> NumberOrString {
+        NumberOrString::String(self.code.to_owned())
+    }
+
+    /// Returns a link to the description of this kind in the catalogue.
+    pub fn get_code_description(&self) -> Option<CodeDescription> {
+        let href = format!("{}#{}", CATALOGUE_URL, self.code.to_lowercase());
+        Url::parse(&href).ok().map(|href| CodeDescription { href })
+    }
+}
+
+/// Returns the locations of the other declarations of the identifier at
+/// `loc`, or nothing if `loc` is not itself a declaration of that identifier.
+///
+/// This is used to point from an error at a declaration to the declarations
+/// with the same name, which are usually the cause of that error. It works on
+/// tokens instead of the IR, since a model with duplicate definitions does not
+/// have a valid IR. An identifier counts as declared if it is inside a
+/// declaring section (e.g. `map`, but not `eqn`), directly follows the section
+/// keyword or the `;` of an earlier declaration, and is followed by a `:`,
+/// `,`, `=` or `(`.
+pub fn get_other_declarations(
+    tokens: &[Token],
+    input: &str,
+    loc: SourceRange,
+) -> Vec<SourceRange> {
+    use LexicalElement::*;
+
+    let identifier = get_source_slice(input, loc);
+    if identifier.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    let mut is_declaration = false;
+    let mut in_declaring_section = false;
+    for (index, token) in tokens.iter().enumerate() {
+        match token.value {
+            Act | Cons | Glob | Map | Proc | Sort | Var => in_declaring_section = true,
+            Eqn | Init => in_declaring_section = false,
+            _ => {},
+        }
+        if !in_declaring_section || !matches!(token.value, Identifier(_)) {
+            continue;
+        }
+        if get_source_slice(input, token.loc) != identifier {
+            continue;
+        }
+        let follows_declaration_start = index > 0 && matches!(
+            tokens[index - 1].value,
+            Act | Cons | Glob | Map | Proc | Sort | Var | Semicolon,
+        );
+        let precedes_declaration_body = matches!(
+            tokens.get(index + 1).map(|token| &token.value),
+            Some(Colon | Comma | Equals | OpeningParen),
+        );
+        if !follows_declaration_start || !precedes_declaration_body {
+            continue;
+        }
+        if range_equals(token.loc, loc) {
+            is_declaration = true;
+        } else {
+            result.push(token.loc);
+        }
+    }
+    if !is_declaration {
+        result.clear();
+    }
+    result
+}
@@ -0,0 +1,213 @@
+use crate::def_info::{get_def_info, DefInfoDisplay};
+use crate::source_mapping::get_head_symbol;
+use crate::struct_sort::get_struct_constructors;
+use crate::util::{get_source_slice, get_token_index, is_section_keyword};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDecl, IrDeclEnum};
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::expr::{BinderExprOp, IrExprEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_def_data, get_defining_node_from_def};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId};
+
+/// Returns a Markdown description of a definition, consisting of its
+/// signature, the doc comments that precede it and the place where it is
+/// declared.
+///
+/// Depending on the kind of definition, this also contains the expansion of a
+/// sort alias, the constructors of a struct sort or the equations that define
+/// a map.
+pub fn get_def_documentation(
+    context: &AnalysisContext,
+    module: &IrModule,
+    file_name: &str,
+    tokens: &[Token],
+    input: &str,
+    def: DefId,
+) -> String {
+    let def_info = get_def_info(context, module, def);
+    let signature = match get_defining_node_from_def(module, def) {
+        DefiningNode::Decl(IrDecl { identifier, value: IrDeclEnum::SortAlias { sort }, .. }) => {
+            let expansion = get_source_slice(input, module.get_node_loc((*sort).into()));
+            format!("sort {} = {}", identifier, expansion)
+        },
+        _ => DefInfoDisplay::new(module, &def_info).to_string(),
+    };
+    let mut result = String::new();
+    push_code_block(&mut result, &signature);
+
+    let identifier_loc = get_def_data(module, module.get_def_source(def)).unwrap().2;
+    if let Some(doc_comment) = get_doc_comment(tokens, input, identifier_loc) {
+        result.push_str("\n\n");
+        result.push_str(&doc_comment);
+    }
+
+    result.push_str("\n\n");
+    result.push_str(&get_declaration_description(module, def));
+    let file_short_name = file_name.rsplit('/').next().unwrap_or(file_name);
+    let line = identifier_loc.get_start_line() + 1;
+    result.push_str(&format!(" at [{}:{}]({}#L{})", file_short_name, line, file_name, line));
+
+    let constructors = get_struct_constructors(module, def);
+    if !constructors.is_empty() {
+        result.push_str("\n\n**Constructors**\n");
+        for constructor in constructors {
+            let constructor_info = get_def_info(context, module, constructor);
+            result.push_str(&format!(
+                "\n- `{}`",
+                DefInfoDisplay::new(module, &constructor_info),
+            ));
+        }
+    }
+
+    let equations = get_map_equations(context, module, def)
+        .into_iter()
+        .map(|loc| get_source_slice(input, loc))
+        .collect::<Vec<_>>();
+    if !equations.is_empty() {
+        result.push_str("\n\n**Equations**\n\n");
+        push_code_block(&mut result, &equations.join("\n"));
+    }
+    result
+}
+
+/// Returns a Markdown description of the sort of a data expression, or `None`
+/// if its sort could not be resolved.
+///
+/// Short expressions are shown together with their sort, e.g. `x + 1: Nat`.
+pub fn get_expr_documentation(
+    context: &AnalysisContext,
+    module: &IrModule,
+    input: &str,
+    expr: ExprId,
+) -> Option<String> {
+    const MAX_EXPR_LENGTH: usize = 60;
+
+    let sort = query_resolved_sort(context, expr).ok()?;
+    let sort_string = ResolvedSortDisplay::new(module, &sort).to_string();
+    let expr_text = get_source_slice(input, module.get_node_loc(expr.into()));
+    let mut result = String::new();
+    if !expr_text.contains('\n') && expr_text.chars().count() <= MAX_EXPR_LENGTH {
+        push_code_block(&mut result, &format!("{}: {}", expr_text, sort_string));
+    } else {
+        push_code_block(&mut result, &sort_string);
+    }
+    Some(result)
+}
+
+/// Returns the text of the doc comments that directly precede the definition
+/// whose identifier is at `identifier_loc`, or `None` if there are none.
+///
+/// A doc comment may be placed either before the identifier itself (e.g.
+/// inside a `map` section with multiple maps) or before the keyword that
+/// starts the section (e.g. `map f: Nat;`).
+pub fn get_doc_comment(
+    tokens: &[Token],
+    input: &str,
+    identifier_loc: SourceRange,
+) -> Option<String> {
+    let index = get_token_index(tokens, identifier_loc)?;
+
+    let mut end = index;
+    if end > 0 && is_section_keyword(&tokens[end - 1].value) {
+        end -= 1;
+    }
+    let mut start = end;
+    while start > 0 && matches!(tokens[start - 1].value, LexicalElement::DocComment(_)) {
+        start -= 1;
+    }
+    if start == end {
+        return None;
+    }
+
+    let lines = tokens[start..end]
+        .iter()
+        .map(|token| {
+            let text = get_source_slice(input, token.loc).trim_start_matches('%');
+            text.strip_prefix(' ').unwrap_or(text).trim_end()
+        })
+        .collect::<Vec<_>>();
+    Some(lines.join("\n"))
+}
+
+/// Returns the source locations of all equations whose left-hand side has the
+/// given map as its head symbol, or an empty list if `def` is not a map.
+pub fn get_map_equations(
+    context: &AnalysisContext,
+    module: &IrModule,
+    def: DefId,
+) -> Vec<SourceRange> {
+    let is_map = matches!(
+        get_defining_node_from_def(module, def),
+        DefiningNode::Decl(decl) if matches!(decl.value, IrDeclEnum::Map { .. }),
+    );
+    if !is_map {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    for node in module {
+        let NodeId::RewriteRule(rule_id) = node else {
+            continue;
+        };
+        let rule = module.get_rewrite_rule(rule_id);
+        let Some(head) = get_head_symbol(module, rule.lhs) else {
+            continue;
+        };
+        if query_def_of_name(context, head.into()) == Ok(def) {
+            result.push(module.get_node_loc(node));
+        }
+    }
+    result
+}
+
+/// Returns a short description of where a definition is introduced, such as
+/// "Declared in a `map` section" or "Parameter of `Main`".
+fn get_declaration_description(module: &IrModule, def: DefId) -> String {
+    let def_node = module.get_def_source(def);
+    match get_defining_node_from_def(module, def) {
+        DefiningNode::Decl(decl) => {
+            format!("Declared in a `{}` section", decl.value.get_keyword_string())
+        },
+        DefiningNode::Param(_) => {
+            match module.get_parent(def_node) {
+                Some(NodeId::Decl(decl_id)) => {
+                    format!("Parameter of `{}`", module.get_decl(decl_id).identifier)
+                },
+                _ => "Parameter".to_owned(),
+            }
+        },
+        DefiningNode::BinderExpr { .. } => {
+            let NodeId::Expr(expr_id) = def_node else {
+                return "Bound variable".to_owned()
+            };
+            match module.get_expr(expr_id).value {
+                IrExprEnum::Binder { op: BinderExprOp::Exists, .. } => {
+                    "Variable bound by `exists`".to_owned()
+                },
+                IrExprEnum::Binder { op: BinderExprOp::Forall, .. } => {
+                    "Variable bound by `forall`".to_owned()
+                },
+                IrExprEnum::Binder { op: BinderExprOp::Lambda, .. } => {
+                    "Variable bound by `lambda`".to_owned()
+                },
+                IrExprEnum::Binder { op: BinderExprOp::SetComprehension, .. } => {
+                    "Variable bound by a comprehension".to_owned()
+                },
+                _ => "Bound variable".to_owned(),
+            }
+        },
+        DefiningNode::SumProc { .. } => "Variable bound by `sum`".to_owned(),
+        DefiningNode::RewriteVar(_) => "Declared in a `var` section".to_owned(),
+    }
+}
+
+fn push_code_block(result: &mut String, code: &str) {
+    result.push_str("```mcrl2\n");
+    result.push_str(code);
+    result.push_str("\n```");
+}
@@ -0,0 +1,168 @@
+use crate::source_mapping::IdentifierIterator;
+use crate::struct_generation::format_declarations;
+use crate::util::{
+    get_declaration_insert_line, get_source_slice, get_token_index, range_contains,
+    range_equals, source_range_to_lsp_range, split_at_top_level,
+};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::Token;
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId};
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// The name of the extracted map, before making it unique.
+const EXTRACTED_MAP_NAME: &str = "extracted";
+
+/// A variable that occurs in the extracted expression but is bound outside of
+/// it, which becomes a parameter of the extracted map.
+struct FreeVariable {
+    def_id: DefId,
+    name: String,
+    sort: String,
+}
+
+/// Returns the code action that extracts the data expression at `loc` into a
+/// new map, whose parameters are the variables of the expression that are
+/// bound outside of it.
+///
+/// The map, its variables and its equation are added before the section that
+/// contains the expression (or before its `var` section, for an `eqn`
+/// section), and the expression is replaced by a call to the map.
+pub fn get_extract_map_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    uri: &Url,
+    loc: SourceRange,
+) -> Vec<CodeAction> {
+    let expr_node = module.into_iter().find(|&node| {
+        matches!(node, NodeId::Expr(_)) && range_equals(module.get_node_loc(node), loc)
+    });
+    let Some(expr_node) = expr_node else {
+        return Vec::new()
+    };
+    let Some(variables) = get_free_variables(context, module, expr_node, loc) else {
+        return Vec::new()
+    };
+    let Ok(result_sort) = query_resolved_sort(context, expr_node) else {
+        return Vec::new()
+    };
+    let result_sort = ResolvedSortDisplay::new(module, &result_sort).to_string();
+    let Some(start_index) = get_token_index(tokens, loc) else {
+        return Vec::new()
+    };
+
+    let name = get_unused_decl_name(module, EXTRACTED_MAP_NAME);
+
+    let (map, call) = if variables.is_empty() {
+        (format!("{}: {}", name, result_sort), name.clone())
+    } else {
+        let sorts = variables.iter()
+            .map(|variable| {
+                // a function sort binds weaker than `#`, e.g. `(Nat -> Bool) # Nat`
+                if split_at_top_level(&variable.sort, "->").len() > 1 {
+                    format!("({})", variable.sort)
+                } else {
+                    variable.sort.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+        let names = variables.iter().map(|variable| variable.name.as_str()).collect::<Vec<_>>();
+        (
+            format!("{}: {} -> {}", name, sorts.join(" # "), result_sort),
+            format!("{}({})", name, names.join(", ")),
+        )
+    };
+    let declarations = variables.iter()
+        .map(|variable| format!("{}: {}", variable.name, variable.sort))
+        .collect::<Vec<_>>();
+    let equation = format!("{} = {}", call, get_source_slice(input, loc));
+
+    let position = Position::new(get_declaration_insert_line(tokens, start_index), 0);
+    let edits = vec![
+        TextEdit::new(
+            Range::new(position, position),
+            format_declarations(&map, &declarations, &[equation]),
+        ),
+        TextEdit::new(source_range_to_lsp_range(loc), call),
+    ];
+    vec![CodeAction {
+        title: format!("Extract expression into map `{}`", name),
+        kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        ..CodeAction::default()
+    }]
+}
+
+/// Returns the variables that are used in the expression at `expr_loc` but
+/// that are bound outside of it, in the order in which they first occur, or
+/// `None` if the sort of one of them could not be determined.
+fn get_free_variables(
+    context: &AnalysisContext,
+    module: &IrModule,
+    expr_node: NodeId,
+    expr_loc: SourceRange,
+) -> Option<Vec<FreeVariable>> {
+    let mut result: Vec<FreeVariable> = Vec::new();
+    for (identifier, _, node, def_id) in IdentifierIterator::new(module, expr_node) {
+        if def_id.is_some() {
+            continue;
+        }
+        let Ok(def_id) = query_def_of_name(context, node) else {
+            continue;
+        };
+        let def_source = module.get_def_source(def_id);
+        if range_contains(expr_loc, module.get_node_loc(def_source)) ||
+            result.iter().any(|variable| variable.def_id == def_id)
+        {
+            continue;
+        }
+        let is_variable = match get_defining_node_from_def(module, def_id) {
+            DefiningNode::Decl(decl) => matches!(decl.value, IrDeclEnum::GlobalVariable { .. }),
+            DefiningNode::BinderExpr { .. } |
+            DefiningNode::Param(_) |
+            DefiningNode::RewriteVar(_) |
+            DefiningNode::SumProc { .. } => true,
+        };
+        if !is_variable {
+            continue;
+        }
+        let sort = query_resolved_sort(context, node).ok()?;
+        result.push(FreeVariable {
+            def_id,
+            name: identifier.to_string(),
+            sort: ResolvedSortDisplay::new(module, &sort).to_string(),
+        });
+    }
+    Some(result)
+}
+
+/// Returns `name`, followed by a number if that is needed to make it differ
+/// from all declared names, e.g. `extracted2`.
+pub fn get_unused_decl_name(module: &IrModule, name: &str) -> String {
+    let is_declared = |candidate: &str| {
+        module.into_iter().any(|node| match node {
+            NodeId::Decl(decl_id) => module.get_decl(decl_id).identifier.to_string() == candidate,
+            _ => false,
+        })
+    };
+    let mut result = name.to_owned();
+    let mut counter = 1;
+    while is_declared(&result) {
+        counter += 1;
+        result = format!("{}{}", name, counter);
+    }
+    result
+}
@@ -0,0 +1,106 @@
+use crate::extract_map::get_unused_decl_name;
+use crate::source_mapping::{get_def_context_at_loc, IdentifierIterator};
+use crate::util::{
+    get_section_start_line, get_source_slice, get_token_index, range_equals,
+    source_range_to_lsp_range,
+};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::Token;
+use nano_crl2::core::syntax::{SourceCursorPos, SourceRange};
+use nano_crl2::ir::decl::DefId;
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId};
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// The name of the extracted process, before making it unique.
+const EXTRACTED_PROCESS_NAME: &str = "Extracted";
+
+/// Returns the code action that extracts the process expression at `loc` into
+/// a new process, whose parameters are the process parameters and `sum`
+/// variables that are visible at `loc` and used in the expression.
+///
+/// The process is added before the section that contains the expression, and
+/// the expression is replaced by a call to the process.
+pub fn get_extract_process_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    uri: &Url,
+    loc: SourceRange,
+) -> Vec<CodeAction> {
+    let proc_node = module.into_iter().find(|&node| {
+        matches!(node, NodeId::Proc(_)) && range_equals(module.get_node_loc(node), loc)
+    });
+    let Some(proc_node) = proc_node else {
+        return Vec::new()
+    };
+    let Some(start_index) = get_token_index(tokens, loc) else {
+        return Vec::new()
+    };
+    let cursor = SourceCursorPos::new(loc.get_start_line(), loc.get_start_char());
+    let Ok(visible) = get_def_context_at_loc(module, cursor) else {
+        return Vec::new()
+    };
+
+    // the visible variables that are used, in the order of their first use
+    let mut variables: Vec<(DefId, String, String)> = Vec::new();
+    for (identifier, _, node, def_id) in IdentifierIterator::new(module, proc_node) {
+        if def_id.is_some() {
+            continue;
+        }
+        let Ok(def_id) = query_def_of_name(context, node) else {
+            continue;
+        };
+        if visible.contains(&def_id) && !variables.iter().any(|(other, _, _)| *other == def_id) {
+            let sort = match get_defining_node_from_def(module, def_id) {
+                DefiningNode::Param(param) => query_resolved_sort(context, param.sort),
+                DefiningNode::SumProc { sort, .. } => query_resolved_sort(context, sort),
+                _ => continue,
+            };
+            let Ok(sort) = sort else {
+                return Vec::new()
+            };
+            let sort = ResolvedSortDisplay::new(module, &sort).to_string();
+            variables.push((def_id, identifier.to_string(), sort));
+        }
+    }
+
+    let name = get_unused_decl_name(module, EXTRACTED_PROCESS_NAME);
+    let params = variables.iter()
+        .map(|(_, identifier, sort)| format!("{}: {}", identifier, sort))
+        .collect::<Vec<_>>();
+    let args = variables.iter()
+        .map(|(_, identifier, _)| identifier.as_str())
+        .collect::<Vec<_>>();
+    let (header, call) = if variables.is_empty() {
+        (name.clone(), name.clone())
+    } else {
+        (
+            format!("{}({})", name, params.join(", ")),
+            format!("{}({})", name, args.join(", ")),
+        )
+    };
+
+    let position = Position::new(get_section_start_line(tokens, start_index), 0);
+    let declaration = format!("proc {} = {};\n\n", header, get_source_slice(input, loc));
+    let edits = vec![
+        TextEdit::new(Range::new(position, position), declaration),
+        TextEdit::new(source_range_to_lsp_range(loc), call),
+    ];
+    vec![CodeAction {
+        title: format!("Extract process `{}`", name),
+        kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        ..CodeAction::default()
+    }]
+}
@@ -0,0 +1,147 @@
+use crate::util::{is_comment, is_section_keyword};
+
+use nano_crl2::core::lexer::{LexicalElement, Token};
+
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+
+use std::collections::HashSet;
+
+/// Returns the folding ranges of a model, namely:
+/// - each section, from its keyword up to its last declaration
+/// - the constructors of struct sorts
+/// - the bodies of processes, from the `=` up to the `;`
+/// - expressions in parentheses, brackets or braces, where the closing token
+///   stays visible if it starts a line
+/// - consecutive lines that consist of comments
+///
+/// Only ranges that span multiple lines are returned. They are computed from
+/// the tokens, so that they are available while the model cannot be parsed.
+pub fn get_folding_ranges(tokens: &[Token]) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    add_section_ranges(tokens, &mut ranges);
+    add_bracket_ranges(tokens, &mut ranges);
+    add_comment_ranges(tokens, &mut ranges);
+
+    let mut seen = HashSet::new();
+    ranges.into_iter()
+        .filter(|(start, end, _)| start < end)
+        .filter(|&(start, end, _)| seen.insert((start, end)))
+        .map(|(start_line, end_line, kind)| FoldingRange {
+            start_line,
+            end_line,
+            kind: Some(kind),
+            ..FoldingRange::default()
+        })
+        .collect()
+}
+
+type LineRange = (u32, u32, FoldingRangeKind);
+
+fn add_section_ranges(tokens: &[Token], ranges: &mut Vec<LineRange>) {
+    let mut keyword: Option<usize> = None;
+    let mut decl_start: Option<usize> = None;
+    let mut last_token: Option<usize> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        if is_comment(&token.value) {
+            continue;
+        }
+        if is_section_keyword(&token.value) {
+            if let (Some(keyword), Some(last_token)) = (keyword, last_token) {
+                ranges.push(get_line_range(tokens, keyword, last_token, FoldingRangeKind::Region));
+            }
+            keyword = Some(index);
+            decl_start = None;
+            last_token = None;
+            continue;
+        }
+        last_token = Some(index);
+        let start = *decl_start.get_or_insert(index);
+        if !matches!(token.value, LexicalElement::Semicolon) {
+            continue;
+        }
+        decl_start = None;
+
+        // the declaration from `start` up to and including `index`
+        let Some(keyword) = keyword else {
+            continue;
+        };
+        let decl = start..index;
+        match tokens[keyword].value {
+            LexicalElement::Proc => {
+                let equals = decl.clone()
+                    .find(|&i| matches!(tokens[i].value, LexicalElement::Equals));
+                if let Some(equals) = equals {
+                    ranges.push(get_line_range(tokens, equals, index, FoldingRangeKind::Region));
+                }
+            },
+            LexicalElement::Sort => {
+                let struct_index = decl.clone()
+                    .find(|&i| matches!(tokens[i].value, LexicalElement::Struct));
+                if let Some(struct_index) = struct_index {
+                    let kind = FoldingRangeKind::Region;
+                    ranges.push(get_line_range(tokens, struct_index, index, kind));
+                }
+            },
+            _ => {},
+        }
+    }
+    if let (Some(keyword), Some(last_token)) = (keyword, last_token) {
+        ranges.push(get_line_range(tokens, keyword, last_token, FoldingRangeKind::Region));
+    }
+}
+
+fn add_bracket_ranges(tokens: &[Token], ranges: &mut Vec<LineRange>) {
+    use LexicalElement::*;
+
+    let mut open_brackets = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match token.value {
+            OpeningBrace | OpeningBracket | OpeningParen => open_brackets.push(index),
+            ClosingBrace | ClosingBracket | ClosingParen => {
+                let Some(open) = open_brackets.pop() else {
+                    continue;
+                };
+                let start_line = tokens[open].loc.get_start_line();
+                let mut end_line = token.loc.get_start_line();
+                let starts_line = tokens[index - 1].loc.get_end_line() < end_line;
+                if starts_line {
+                    end_line -= 1;
+                }
+                ranges.push((start_line, end_line, FoldingRangeKind::Region));
+            },
+            _ => {},
+        }
+    }
+}
+
+fn add_comment_ranges(tokens: &[Token], ranges: &mut Vec<LineRange>) {
+    let mut block: Option<(u32, u32)> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let is_own_line = index == 0 ||
+            tokens[index - 1].loc.get_end_line() < token.loc.get_start_line();
+        let line = token.loc.get_start_line();
+        block = match block {
+            Some((start, end)) if is_comment(&token.value) && is_own_line && line == end + 1 => {
+                Some((start, line))
+            },
+            _ => {
+                if let Some((start, end)) = block {
+                    ranges.push((start, end, FoldingRangeKind::Comment));
+                }
+                (is_comment(&token.value) && is_own_line).then_some((line, line))
+            },
+        };
+    }
+    if let Some((start, end)) = block {
+        ranges.push((start, end, FoldingRangeKind::Comment));
+    }
+}
+
+fn get_line_range(
+    tokens: &[Token],
+    first: usize,
+    last: usize,
+    kind: FoldingRangeKind,
+) -> LineRange {
+    (tokens[first].loc.get_start_line(), tokens[last].loc.get_end_line(), kind)
+}
@@ -0,0 +1,873 @@
+use crate::util::{
+    get_source_slice, is_comment, is_section_keyword, lsp_range_to_source_range,
+    source_range_to_lsp_range,
+};
+
+use nano_crl2::core::lexer::{LexicalElement, Token};
+
+use tower_lsp::lsp_types::{Position, Range as LspRange, TextEdit};
+
+use std::ops::{Range, RangeInclusive};
+
+/// The width that formatted lines should not exceed, if possible.
+const MAX_LINE_WIDTH: usize = 100;
+
+/// A part of a model at the top level, outside of declarations.
+enum Item {
+    Comment(usize),
+    Section(Section),
+}
+
+/// A section keyword, such as `map`, with the declarations that follow it.
+struct Section {
+    keyword: usize,
+    /// A comment after the keyword on the same line.
+    trailing_comment: Option<usize>,
+    entries: Vec<Entry>,
+}
+
+/// A part of a section, where comments are the ones on their own line.
+enum Entry {
+    Comment(usize),
+    Decl(Decl),
+}
+
+/// A declaration, equation or process expression that is ended by `;`.
+struct Decl {
+    /// The tokens of the declaration, including the `;`.
+    tokens: Range<usize>,
+    /// A comment after the `;` on the same line.
+    trailing_comment: Option<usize>,
+}
+
+impl Entry {
+    fn get_last_token(&self) -> usize {
+        match self {
+            Entry::Comment(index) => *index,
+            Entry::Decl(decl) => decl.trailing_comment.unwrap_or(decl.tokens.end - 1),
+        }
+    }
+
+    fn get_first_token(&self) -> usize {
+        match self {
+            Entry::Comment(index) => *index,
+            Entry::Decl(decl) => decl.tokens.start,
+        }
+    }
+}
+
+impl Section {
+    /// Returns the declaration of a section that consists of one declaration,
+    /// which is put on the line of the keyword.
+    fn get_single_decl(&self) -> Option<&Decl> {
+        match (self.trailing_comment, &self.entries[..]) {
+            (None, [Entry::Decl(decl)]) => Some(decl),
+            _ => None,
+        }
+    }
+
+    fn get_last_token(&self) -> usize {
+        match self.entries.last() {
+            Some(entry) => entry.get_last_token(),
+            None => self.trailing_comment.unwrap_or(self.keyword),
+        }
+    }
+}
+
+/// Returns the formatted text of a model, or `None` if it contains tokens
+/// outside of sections.
+///
+/// The formatter works on the tokens of the model, which means that all
+/// comments are kept, and it should only be used for models that can be
+/// parsed. It normalizes the spacing between tokens and the indentation of
+/// sections, where a section with a single declaration is put on one line
+/// (as in `map f: Nat -> Nat;`) and the declarations of other sections are put
+/// on their own lines below the keyword. The names of the variables in a `var`
+/// section are aligned, and declarations that are too long are split up:
+/// - struct sorts get one constructor per line, or one parameter per line if
+///   there is only a single constructor
+/// - process expressions are split at the operators that bind the weakest,
+///   such as `+`
+///
+/// Empty lines are kept, but multiple empty lines are merged into one.
+pub fn format_model(tokens: &[Token], input: &str, indent: &str) -> Option<String> {
+    let formatter = Formatter { tokens, input, indent };
+    let items = formatter.parse()?;
+
+    let mut lines = Vec::new();
+    let mut previous_token: Option<usize> = None;
+    for item in &items {
+        let (first_token, last_token) = match item {
+            Item::Comment(index) => (*index, *index),
+            Item::Section(section) => (section.keyword, section.get_last_token()),
+        };
+        if previous_token.is_some_and(|previous| formatter.has_empty_line(previous, first_token)) {
+            lines.push(String::new());
+        }
+        match item {
+            Item::Comment(index) => lines.push(formatter.get_text(*index).to_owned()),
+            Item::Section(section) => formatter.format_section(section, &mut lines),
+        }
+        previous_token = Some(last_token);
+    }
+
+    let mut result = lines.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n");
+    result.push('\n');
+    Some(result)
+}
+
+/// Returns the edits that format the declarations that overlap with `range`,
+/// in the same way as `format_model`.
+///
+/// A section with a single declaration on the line of its keyword is
+/// formatted as a whole.
+pub fn format_range(
+    tokens: &[Token],
+    input: &str,
+    indent: &str,
+    range: LspRange,
+) -> Option<Vec<TextEdit>> {
+    let formatter = Formatter { tokens, input, indent };
+    let items = formatter.parse()?;
+
+    let overlaps = |first: usize, last: usize| {
+        source_range_to_lsp_range(tokens[first].loc).start <= range.end &&
+            range.start <= source_range_to_lsp_range(tokens[last].loc).end
+    };
+    let mut result = Vec::new();
+    for item in &items {
+        let Item::Section(section) = item else {
+            continue;
+        };
+        if section.get_single_decl().is_some() {
+            if overlaps(section.keyword, section.get_last_token()) {
+                let mut lines = Vec::new();
+                formatter.format_section(section, &mut lines);
+                result.extend(formatter.get_replacement(
+                    section.keyword,
+                    section.get_last_token(),
+                    &lines,
+                ));
+            }
+            continue;
+        }
+        let names_width = formatter.get_names_width(section);
+        for entry in &section.entries {
+            let (first, last) = (entry.get_first_token(), entry.get_last_token());
+            if overlaps(first, last) {
+                let lines = formatter.format_entry(section, entry, names_width);
+                result.extend(formatter.get_replacement(first, last, &lines));
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Returns the edits that re-indent the declaration that contains the line of
+/// `position`, after `typed` was typed there, which is either `;` or a new
+/// line.
+///
+/// Unlike the other kinds of formatting, this only changes the indentation of
+/// lines, so that the layout of a declaration that is being typed is kept.
+/// After a new line, the declaration before it is re-indented as well, and
+/// the new line gets the indentation of the declaration that it is part of.
+pub fn format_on_type(
+    tokens: &[Token],
+    input: &str,
+    indent: &str,
+    position: Position,
+    typed: &str,
+) -> Option<Vec<TextEdit>> {
+    let formatter = Formatter { tokens, input, indent };
+    let items = formatter.parse()?;
+    let is_new_line = typed == "\n";
+    let target_lines = if is_new_line {
+        position.line.saturating_sub(1)..=position.line
+    } else {
+        position.line..=position.line
+    };
+
+    for item in &items {
+        let Item::Section(section) = item else {
+            continue;
+        };
+        for entry in &section.entries {
+            let Entry::Decl(decl) = entry else {
+                continue;
+            };
+            let first_line = tokens[decl.tokens.start].loc.get_start_line();
+            let mut last_line = tokens[entry.get_last_token()].loc.get_end_line();
+            // a declaration that is not finished yet continues up to the next
+            // token
+            let last_value = &tokens[decl.tokens.end - 1].value;
+            if !matches!(last_value, LexicalElement::Semicolon) {
+                let next_line = tokens.get(entry.get_last_token() + 1)
+                    .map_or(u32::MAX, |token| token.loc.get_start_line());
+                last_line = last_line.max(next_line.saturating_sub(1).min(position.line));
+            }
+            if *target_lines.end() < first_line || last_line < *target_lines.start() {
+                continue;
+            }
+            let base_indent = if section.get_single_decl().is_some() { "" } else { indent };
+            let lines = first_line..=last_line;
+            let new_line = is_new_line.then_some(position.line);
+            return Some(formatter.get_indent_edits(decl, lines, new_line, base_indent));
+        }
+    }
+    Some(Vec::new())
+}
+
+struct Formatter<'a> {
+    tokens: &'a [Token],
+    input: &'a str,
+    /// The text of one level of indentation.
+    indent: &'a str,
+}
+
+impl<'a> Formatter<'a> {
+    fn parse(&self) -> Option<Vec<Item>> {
+        let mut result = Vec::new();
+        let mut index = 0;
+        while index < self.tokens.len() {
+            if is_comment(&self.tokens[index].value) {
+                result.push(Item::Comment(index));
+                index += 1;
+                continue;
+            }
+            if !is_section_keyword(&self.tokens[index].value) {
+                return None;
+            }
+
+            let keyword = index;
+            index += 1;
+            let trailing_comment = self.get_trailing_comment(keyword, index);
+            if trailing_comment.is_some() {
+                index += 1;
+            }
+            let mut entries = Vec::new();
+            while index < self.tokens.len() && !is_section_keyword(&self.tokens[index].value) {
+                if is_comment(&self.tokens[index].value) {
+                    entries.push(Entry::Comment(index));
+                    index += 1;
+                    continue;
+                }
+                let start = index;
+                let mut depth = 0;
+                while index < self.tokens.len() && !is_section_keyword(&self.tokens[index].value) {
+                    let value = &self.tokens[index].value;
+                    depth += get_depth_change(value);
+                    index += 1;
+                    if depth == 0 && matches!(value, LexicalElement::Semicolon) {
+                        break;
+                    }
+                }
+                let tokens = start..index;
+                let trailing_comment = self.get_trailing_comment(index - 1, index);
+                if trailing_comment.is_some() {
+                    index += 1;
+                }
+                entries.push(Entry::Decl(Decl { tokens, trailing_comment }));
+            }
+
+            // the comments after the last declaration precede the next section
+            let comment_count = entries.iter()
+                .rev()
+                .take_while(|entry| matches!(entry, Entry::Comment(_)))
+                .count();
+            let comments = entries.split_off(entries.len() - comment_count);
+            result.push(Item::Section(Section { keyword, trailing_comment, entries }));
+            result.extend(comments.into_iter().map(|entry| Item::Comment(entry.get_first_token())));
+        }
+        Some(result)
+    }
+
+    /// Returns `index` if it is a comment on the same line as the token at
+    /// `previous`.
+    fn get_trailing_comment(&self, previous: usize, index: usize) -> Option<usize> {
+        let token = self.tokens.get(index)?;
+        let is_trailing = is_comment(&token.value) &&
+            token.loc.get_start_line() == self.tokens[previous].loc.get_end_line();
+        is_trailing.then_some(index)
+    }
+
+    fn format_section(&self, section: &Section, lines: &mut Vec<String>) {
+        let keyword = &self.tokens[section.keyword].value;
+        let keyword_text = self.get_text(section.keyword);
+
+        if let Some(decl) = section.get_single_decl() {
+            let first_column = keyword_text.chars().count() + 1;
+            let decl_lines = self.format_decl(keyword, decl, "", first_column);
+            if decl_lines[0].is_empty() {
+                lines.push(keyword_text.to_owned());
+            } else {
+                lines.push(format!("{} {}", keyword_text, decl_lines[0]));
+            }
+            lines.extend(decl_lines.into_iter().skip(1));
+            return;
+        }
+
+        match section.trailing_comment {
+            Some(comment) => lines.push(format!("{} {}", keyword_text, self.get_text(comment))),
+            None => lines.push(keyword_text.to_owned()),
+        }
+        let names_width = self.get_names_width(section);
+        let mut previous_token = section.trailing_comment.unwrap_or(section.keyword);
+        for entry in &section.entries {
+            if self.has_empty_line(previous_token, entry.get_first_token()) {
+                lines.push(String::new());
+            }
+            previous_token = entry.get_last_token();
+            lines.extend(self.format_entry(section, entry, names_width));
+        }
+    }
+
+    /// Returns the lines of an entry of a section that has its entries below
+    /// the keyword.
+    fn format_entry(
+        &self,
+        section: &Section,
+        entry: &Entry,
+        names_width: Option<usize>,
+    ) -> Vec<String> {
+        let decl = match entry {
+            Entry::Comment(index) => {
+                return vec![format!("{}{}", self.indent, self.get_text(*index))]
+            },
+            Entry::Decl(decl) => decl,
+        };
+        if let (Some(width), Some((_, colon))) = (names_width, self.get_var_colon(decl)) {
+            let names = self.render_line(decl.tokens.start..colon);
+            let mut line = format!(
+                "{}{:<width$}{}",
+                self.indent,
+                names,
+                self.render_line(colon..decl.tokens.end),
+                width = width,
+            );
+            if let Some(comment) = decl.trailing_comment {
+                line.push(' ');
+                line.push_str(self.get_text(comment));
+            }
+            return vec![line];
+        }
+        let keyword = &self.tokens[section.keyword].value;
+        let mut lines = self.format_decl(keyword, decl, self.indent, self.indent.len());
+        lines[0].insert_str(0, self.indent);
+        lines
+    }
+
+    /// Returns the width of the longest list of names in a `var` section, to
+    /// which the names of all variables are aligned.
+    fn get_names_width(&self, section: &Section) -> Option<usize> {
+        if !matches!(self.tokens[section.keyword].value, LexicalElement::Var) {
+            return None;
+        }
+        section.entries.iter()
+            .filter_map(|entry| match entry {
+                Entry::Decl(decl) => self.get_var_colon(decl),
+                Entry::Comment(_) => None,
+            })
+            .map(|(decl, colon)| self.render_line(decl.tokens.start..colon).chars().count())
+            .max()
+    }
+
+    /// Returns the lines of a declaration in a section with the given keyword,
+    /// where the first line starts at `first_column` and is not indented, and
+    /// the other lines are indented relative to `indent`.
+    ///
+    /// The first line is empty if the declaration should start on a new line.
+    /// Declarations that do not fit on a line and that cannot be split up in a
+    /// specific way keep the line breaks of the source.
+    fn format_decl(
+        &self,
+        keyword: &LexicalElement,
+        decl: &Decl,
+        indent: &str,
+        first_column: usize,
+    ) -> Vec<String> {
+        let continuation = format!("{}{}", indent, self.indent);
+        let range = decl.tokens.clone();
+        let has_comments = range.clone().any(|index| is_comment(&self.tokens[index].value));
+        let is_struct = matches!(keyword, LexicalElement::Sort) &&
+            range.clone().any(|index| matches!(self.tokens[index].value, LexicalElement::Struct));
+        let is_multi_line = self.tokens[range.start].loc.get_start_line() <
+            self.tokens[range.end - 1].loc.get_end_line();
+
+        let line = self.render_line(range.clone());
+        let fits = first_column + line.chars().count() <= MAX_LINE_WIDTH;
+        let mut lines = if fits && !has_comments && !(is_struct && is_multi_line) {
+            vec![line]
+        } else {
+            let lines = match keyword {
+                LexicalElement::Sort if is_struct => {
+                    self.format_struct(range.clone(), indent, &continuation)
+                },
+                LexicalElement::Init | LexicalElement::Proc if !has_comments => {
+                    self.format_process(keyword, range.clone(), &continuation)
+                },
+                _ => None,
+            };
+            lines.unwrap_or_else(|| self.render(range, &continuation, true))
+        };
+        if let Some(comment) = decl.trailing_comment {
+            let last = lines.last_mut().unwrap();
+            if !last.trim().is_empty() {
+                last.push(' ');
+            }
+            last.push_str(self.get_text(comment));
+        }
+        lines
+    }
+
+    /// Splits a struct sort declaration into one constructor per line, or into
+    /// one parameter per line if it has a single constructor.
+    fn format_struct(
+        &self,
+        range: Range<usize>,
+        indent: &str,
+        continuation: &str,
+    ) -> Option<Vec<String>> {
+        let struct_index = range.clone()
+            .find(|&index| matches!(self.tokens[index].value, LexicalElement::Struct))?;
+        let end = range.end - 1;
+        if !matches!(self.tokens[end].value, LexicalElement::Semicolon) {
+            return None;
+        }
+
+        let pipes = self.find_top_level(struct_index + 1..end, |value| {
+            matches!(value, LexicalElement::Pipe)
+        });
+        if !pipes.is_empty() {
+            let mut lines = vec![self.render_line(range.start..struct_index + 1)];
+            lines.extend(self.format_items(struct_index + 1..end, &pipes, continuation, ";"));
+            return Some(lines);
+        }
+
+        let open = struct_index + 2;
+        if !matches!(self.tokens.get(open)?.value, LexicalElement::OpeningParen) {
+            return None;
+        }
+        let close = self.find_top_level(open..end, |value| {
+            matches!(value, LexicalElement::ClosingParen)
+        });
+        let close = *close.first()?;
+        let commas = self.find_top_level(open + 1..close, |value| {
+            matches!(value, LexicalElement::Comma)
+        });
+        let mut lines = vec![self.render_line(range.start..open + 1)];
+        lines.extend(self.format_items(open + 1..close, &commas, continuation, ""));
+        lines.push(format!("{}{}", indent, self.render_line(close..range.end)));
+        Some(lines)
+    }
+
+    /// Puts the items in `range` that are separated by the tokens at
+    /// `separators` on their own lines, where the separators end the lines and
+    /// `last_suffix` ends the last line.
+    ///
+    /// A comment after a separator stays on the line of that separator.
+    fn format_items(
+        &self,
+        range: Range<usize>,
+        separators: &[usize],
+        continuation: &str,
+        last_suffix: &str,
+    ) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut start = range.start;
+        for &end in separators.iter().chain([range.end].iter()) {
+            let mut lines = self.render(start..end, continuation, false);
+            lines[0].insert_str(0, continuation);
+            let last = lines.last_mut().unwrap();
+            if end == range.end {
+                last.push_str(last_suffix);
+            } else {
+                if matches!(self.tokens[end].value, LexicalElement::Pipe) {
+                    last.push(' ');
+                }
+                last.push_str(self.get_text(end));
+                start = end + 1;
+                if let Some(comment) = self.get_trailing_comment(end, start) {
+                    last.push(' ');
+                    last.push_str(self.get_text(comment));
+                    start += 1;
+                }
+            }
+            result.extend(lines);
+        }
+        result
+    }
+
+    /// Splits the process expression of a `proc` or `init` declaration over
+    /// multiple lines, which start after the `=` of a process declaration.
+    fn format_process(
+        &self,
+        keyword: &LexicalElement,
+        range: Range<usize>,
+        continuation: &str,
+    ) -> Option<Vec<String>> {
+        let mut end = range.end;
+        if matches!(self.tokens[end - 1].value, LexicalElement::Semicolon) {
+            end -= 1;
+        }
+        let (mut lines, start) = if matches!(keyword, LexicalElement::Proc) {
+            let equals = self.find_top_level(range.start..end, |value| {
+                matches!(value, LexicalElement::Equals)
+            });
+            let equals = *equals.first()?;
+            (vec![self.render_line(range.start..equals + 1)], equals + 1)
+        } else {
+            (vec![String::new()], range.start)
+        };
+        lines.extend(self.wrap_process(start..end, continuation));
+        if end < range.end {
+            lines.last_mut().unwrap().push(';');
+        }
+        Some(lines)
+    }
+
+    /// Splits a process expression at the operators that bind the weakest, as
+    /// long as the lines are too long.
+    fn wrap_process(&self, range: Range<usize>, indent: &str) -> Vec<String> {
+        let line = format!("{}{}", indent, self.render_line(range.clone()));
+        if line.chars().count() <= MAX_LINE_WIDTH {
+            return vec![line];
+        }
+        let level = self.find_top_level(range.clone(), |_| true)
+            .into_iter()
+            .filter_map(|index| get_process_operator_level(&self.tokens[index].value))
+            .min();
+        let Some(level) = level else {
+            return vec![line]
+        };
+
+        let operators = self.find_top_level(range.clone(), |value| {
+            get_process_operator_level(value) == Some(level)
+        });
+        let mut result = Vec::new();
+        let mut start = range.start;
+        for operator in operators {
+            let mut lines = self.wrap_process(start..operator, indent);
+            let last = lines.last_mut().unwrap();
+            last.push(' ');
+            last.push_str(self.get_text(operator));
+            result.extend(lines);
+            start = operator + 1;
+        }
+        result.extend(self.wrap_process(start..range.end, indent));
+        result
+    }
+
+    /// Returns the index of the first `:` of a variable declaration such as
+    /// `x, y: Nat;`, if it does not contain comments.
+    fn get_var_colon<'b>(&self, decl: &'b Decl) -> Option<(&'b Decl, usize)> {
+        use LexicalElement::*;
+
+        let colon = decl.tokens.clone()
+            .find(|&index| !matches!(self.tokens[index].value, Comma | Identifier(_)))?;
+        let is_colon = colon > decl.tokens.start &&
+            matches!(self.tokens[colon].value, Colon);
+        let has_comments = decl.tokens.clone().any(|index| is_comment(&self.tokens[index].value));
+        (is_colon && !has_comments).then_some((decl, colon))
+    }
+
+    /// Returns the indices of the tokens in `range` that are not nested inside
+    /// parentheses, brackets or braces and that satisfy `predicate`.
+    fn find_top_level(
+        &self,
+        range: Range<usize>,
+        predicate: impl Fn(&LexicalElement) -> bool,
+    ) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut depth = 0;
+        for index in range {
+            let value = &self.tokens[index].value;
+            let change = get_depth_change(value);
+            if change < 0 {
+                depth += change;
+            }
+            if depth == 0 && predicate(value) {
+                result.push(index);
+            }
+            if change > 0 {
+                depth += change;
+            }
+        }
+        result
+    }
+
+    /// Renders the tokens in `range`, which do not contain comments, on a
+    /// single line.
+    fn render_line(&self, range: Range<usize>) -> String {
+        self.render(range, "", false).join(" ")
+    }
+
+    /// Renders the tokens in `range`, where every comment ends a line, and
+    /// where all lines but the first start with `continuation`.
+    ///
+    /// If `keep_line_breaks` is set, a line also ends wherever it does in the
+    /// source, and the lines keep their indentation relative to each other.
+    fn render(
+        &self,
+        range: Range<usize>,
+        continuation: &str,
+        keep_line_breaks: bool,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut at_line_start = true;
+        let mut base_column = None;
+        let mut previous: Option<usize> = None;
+        for index in range {
+            let token = &self.tokens[index];
+            let is_comment = is_comment(&token.value);
+            let is_new_line = previous.is_some_and(|previous| {
+                self.tokens[previous].loc.get_end_line() < token.loc.get_start_line()
+            });
+            if !at_line_start && is_new_line && (is_comment || keep_line_breaks) {
+                lines.push(std::mem::take(&mut line));
+                at_line_start = true;
+            }
+
+            match previous {
+                Some(_) if at_line_start => {
+                    line = continuation.to_owned();
+                    if keep_line_breaks && is_new_line {
+                        let column = token.loc.get_start_char() as usize;
+                        let base_column = *base_column.get_or_insert(column);
+                        line.push_str(&" ".repeat(column.saturating_sub(base_column)));
+                    }
+                },
+                Some(_) if is_comment => line.push(' '),
+                Some(previous) => line.push_str(self.get_spacing(previous, index)),
+                None => {},
+            }
+            line.push_str(self.get_text(index));
+            at_line_start = false;
+            if is_comment {
+                lines.push(std::mem::take(&mut line));
+                at_line_start = true;
+            }
+            previous = Some(index);
+        }
+        if !at_line_start || lines.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Returns the whitespace between two consecutive tokens on a line.
+    ///
+    /// Operators that can be unary, such as `-`, keep the spacing of the
+    /// source.
+    fn get_spacing(&self, previous: usize, next: usize) -> &'static str {
+        use LexicalElement::*;
+
+        let (previous_value, next_value) = (&self.tokens[previous].value, &self.tokens[next].value);
+        match (previous_value, next_value) {
+            (_, ClosingBracket | ClosingParen | Colon | Comma | Semicolon) => "",
+            (OpeningBracket | OpeningParen, _) => "",
+            (OpeningBrace, ClosingBrace) => "",
+            (OpeningBrace, _) | (_, ClosingBrace) => " ",
+            (
+                Identifier(_) | ClosingBracket | ClosingParen | Bag | FBag | FSet | List | Set,
+                OpeningParen,
+            ) => "",
+            (Colon | Comma | Semicolon, _) => " ",
+            _ if is_binary_operator(previous_value) || is_binary_operator(next_value) => " ",
+            _ => {
+                let previous_loc = self.tokens[previous].loc;
+                let next_loc = self.tokens[next].loc;
+                let is_adjacent = previous_loc.get_end_line() == next_loc.get_start_line() &&
+                    previous_loc.get_end_char() == next_loc.get_start_char();
+                if is_adjacent { "" } else { " " }
+            },
+        }
+    }
+
+    /// Returns the edit that replaces the tokens from `first` up to and
+    /// including `last` by `lines`, including the indentation if `first` is
+    /// the first token on its line, or `None` if nothing changes.
+    fn get_replacement(&self, first: usize, last: usize, lines: &[String]) -> Option<TextEdit> {
+        let first_loc = self.tokens[first].loc;
+        let last_loc = self.tokens[last].loc;
+        let is_line_start = first == 0 ||
+            self.tokens[first - 1].loc.get_end_line() < first_loc.get_start_line();
+        let start = if is_line_start {
+            Position::new(first_loc.get_start_line(), 0)
+        } else {
+            source_range_to_lsp_range(first_loc).start
+        };
+        let range = LspRange::new(start, source_range_to_lsp_range(last_loc).end);
+
+        let text = lines.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n");
+        let text = if is_line_start { text.as_str() } else { text.trim_start() };
+        let current = get_source_slice(self.input, lsp_range_to_source_range(range));
+        (current != text).then(|| TextEdit::new(range, text.to_owned()))
+    }
+
+    /// Returns the edits that indent the given lines of a declaration, where
+    /// the first line gets `base_indent` if the declaration starts there, and
+    /// the other lines are indented one level further, while keeping their
+    /// indentation relative to each other.
+    ///
+    /// Empty lines are left alone, except for the line that was just added.
+    fn get_indent_edits(
+        &self,
+        decl: &Decl,
+        lines: RangeInclusive<u32>,
+        new_line: Option<u32>,
+        base_indent: &str,
+    ) -> Vec<TextEdit> {
+        let first_token = self.tokens[decl.tokens.start].loc;
+        let continuation = format!("{}{}", base_indent, self.indent);
+        let is_line_start = decl.tokens.start == 0 ||
+            self.tokens[decl.tokens.start - 1].loc.get_end_line() < first_token.get_start_line();
+
+        let mut result = Vec::new();
+        let mut base_column = None;
+        let input_lines = self.input.split('\n').collect::<Vec<_>>();
+        for line in lines {
+            let Some(text) = input_lines.get(line as usize) else {
+                break;
+            };
+            let current = text.chars().take_while(|c| c.is_whitespace()).count();
+            let indent = if line == first_token.get_start_line() {
+                if !is_line_start {
+                    continue;
+                }
+                base_indent.to_owned()
+            } else if current == text.chars().count() {
+                if new_line != Some(line) {
+                    continue;
+                }
+                continuation.clone()
+            } else {
+                let base_column = *base_column.get_or_insert(current);
+                format!("{}{}", continuation, " ".repeat(current.saturating_sub(base_column)))
+            };
+            if text.chars().take(current).collect::<String>() != indent {
+                let start = Position::new(line, 0);
+                let end = Position::new(line, current as u32);
+                result.push(TextEdit::new(LspRange::new(start, end), indent));
+            }
+        }
+        result
+    }
+
+    /// Returns whether there is an empty line between two tokens.
+    fn has_empty_line(&self, previous: usize, next: usize) -> bool {
+        self.tokens[previous].loc.get_end_line() + 1 < self.tokens[next].loc.get_start_line()
+    }
+
+    fn get_text(&self, index: usize) -> &'a str {
+        get_source_slice(self.input, self.tokens[index].loc).trim_end()
+    }
+}
+
+/// Returns whether a token is an operator that always has two operands, which
+/// is surrounded by spaces.
+fn is_binary_operator(value: &LexicalElement) -> bool {
+    use LexicalElement::*;
+
+    matches!(
+        value,
+        Arrow | Concat | ConsOperator | Diamond | DoubleAmpersand | DoubleEquals | DoublePipe |
+            DoublePipeUnderscore | Equals | GreaterThanEquals | LessThanEquals | NotEquals |
+            Period | Plus | QuestionMark | SnocOperator | ThickArrow
+    )
+}
+
+fn get_depth_change(value: &LexicalElement) -> i32 {
+    use LexicalElement::*;
+
+    match value {
+        OpeningBrace | OpeningBracket | OpeningParen => 1,
+        ClosingBrace | ClosingBracket | ClosingParen => -1,
+        _ => 0,
+    }
+}
+
+/// Returns how weakly an operator of process expressions binds, where `+`
+/// binds the weakest, or `None` if the token is not such an operator.
+fn get_process_operator_level(value: &LexicalElement) -> Option<usize> {
+    use LexicalElement::*;
+
+    match value {
+        Plus => Some(0),
+        DoublePipe | DoublePipeUnderscore => Some(1),
+        Arrow => Some(2),
+        Diamond => Some(3),
+        Period => Some(4),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nano_crl2::analysis::context::AnalysisContext;
+    use nano_crl2::analysis::parsing::query_token_list;
+
+    fn tokenize(input: &str) -> Vec<Token> {
+        let mut context = AnalysisContext::new();
+        let module_id = context.add_model_input("test.mcrl2".to_owned(), input.to_owned());
+        query_token_list(&context, module_id).unwrap().to_vec()
+    }
+
+    fn format(input: &str) -> Option<String> {
+        format_model(&tokenize(input), input, "    ")
+    }
+
+    /// Checks that formatting keeps the tokens of `input` and that formatting
+    /// the result again does not change it.
+    fn assert_idempotent(input: &str) {
+        let formatted = format(input).unwrap();
+        let get_values = |input| {
+            tokenize(input).into_iter().map(|token| token.value).collect::<Vec<_>>()
+        };
+        assert_eq!(get_values(&formatted), get_values(input));
+        assert_eq!(format(&formatted).as_deref(), Some(formatted.as_str()));
+    }
+
+    #[test]
+    fn examples_are_formatted_idempotently() {
+        assert_idempotent(include_str!("../../examples/test.mcrl2"));
+        assert_idempotent(include_str!("../../examples/picalc.mcrl2"));
+        assert_idempotent(include_str!("../../examples/crossing_spec.mcrl2"));
+    }
+
+    #[test]
+    fn single_declaration_is_put_on_keyword_line() {
+        assert_eq!(format("map\n  f :Nat->Nat;\n").unwrap(), "map f: Nat -> Nat;\n");
+    }
+
+    #[test]
+    fn var_names_are_aligned() {
+        let input = "var x:Nat;\n  longer , y: Bool;\n";
+        let expected = "var\n    x        : Nat;\n    longer, y: Bool;\n";
+        assert_eq!(format(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn comments_and_single_empty_lines_are_kept() {
+        let input = "eqn\nf(x)=x+1; % comment\n\n\n\ninit   tau;\n";
+        let expected = "eqn f(x) = x + 1; % comment\n\ninit tau;\n";
+        assert_eq!(format(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn long_process_is_split_at_choice() {
+        let actions = ["action"; 12].join(" . ");
+        let input = format!("proc P = {} . P + {} . P;\n", actions, actions);
+        let formatted = format(&input).unwrap();
+        assert!(formatted.lines().count() > 1);
+        assert!(formatted.lines().all(|line| line.len() <= MAX_LINE_WIDTH));
+        assert_idempotent(&input);
+    }
+
+    #[test]
+    fn tokens_outside_sections_are_not_formatted() {
+        assert_eq!(format("f = 1;\n"), None);
+    }
+}
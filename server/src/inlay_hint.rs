@@ -0,0 +1,232 @@
+use crate::util::get_token_index;
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::IrDeclEnum;
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::iterator::{DefiningNode, ParentIterator, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use serde_json::Value;
+
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Position};
+
+/// Determines which kinds of inlay hints are shown, as configured in the
+/// `nano-crl2-lsp.inlayHints` section of the client settings.
+pub struct InlayHintConfig {
+    /// Show the names of process parameters at process calls.
+    pub parameter_names: bool,
+    /// Show the sorts of variables whose sort is not written down, such as
+    /// the variables bound by `whr`.
+    pub inferred_sorts: bool,
+    /// Show the sorts of the arguments and conditions inside `sum` bodies.
+    pub sum_expression_sorts: bool,
+}
+
+impl InlayHintConfig {
+    /// Reads the configuration from the settings section, using the default
+    /// value for every setting that is missing.
+    pub fn from_settings(settings: &Value) -> Self {
+        let default = InlayHintConfig::default();
+        let get_bool = |key: &str, default: bool| {
+            settings.get(key).and_then(Value::as_bool).unwrap_or(default)
+        };
+        InlayHintConfig {
+            parameter_names: get_bool("parameterNames", default.parameter_names),
+            inferred_sorts: get_bool("inferredSorts", default.inferred_sorts),
+            sum_expression_sorts: get_bool("sumExpressionSorts", default.sum_expression_sorts),
+        }
+    }
+}
+
+impl Default for InlayHintConfig {
+    fn default() -> Self {
+        InlayHintConfig {
+            parameter_names: true,
+            inferred_sorts: true,
+            sum_expression_sorts: false,
+        }
+    }
+}
+
+/// Returns all inlay hints in the given module, sorted by position.
+pub fn get_inlay_hints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    config: &InlayHintConfig,
+) -> Vec<InlayHint> {
+    let mut result = Vec::new();
+    if config.parameter_names {
+        add_parameter_name_hints(context, module, &mut result);
+    }
+    if config.inferred_sorts {
+        add_whr_sort_hints(context, module, &mut result);
+        add_binder_sort_hints(context, module, tokens, &mut result);
+    }
+    if config.sum_expression_sorts {
+        add_sum_expression_sort_hints(context, module, &mut result);
+    }
+    result.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    result
+}
+
+/// Adds the parameter names at process calls, e.g. `Main(param1: 0)`, leaving
+/// out arguments that are just a variable with the same name as the
+/// parameter.
+fn add_parameter_name_hints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    result: &mut Vec<InlayHint>,
+) {
+    for node in module {
+        let NodeId::Action(action_id) = node else {
+            continue;
+        };
+        let Ok(def_id) = query_def_of_name(context, node) else {
+            continue;
+        };
+        let DefiningNode::Decl(decl) = get_defining_node_from_def(module, def_id) else {
+            continue;
+        };
+        let IrDeclEnum::Process { params, .. } = &decl.value else {
+            continue;
+        };
+
+        for (&param_id, &arg) in params.iter().zip(&module.get_action(action_id).args) {
+            let param = module.get_param(param_id);
+            let arg_expr = module.get_expr(arg);
+            if matches!(&arg_expr.value, IrExprEnum::Name { identifier } if *identifier == param.identifier) {
+                continue;
+            }
+            result.push(create_hint(
+                start_position(arg_expr.loc),
+                format!("{}:", param.identifier),
+                InlayHintKind::PARAMETER,
+            ));
+        }
+    }
+}
+
+/// Adds the sorts of the variables that are bound by a `whr` clause, such as
+/// the `Nat` in `x + y whr x = 1, y = 2 end`.
+fn add_whr_sort_hints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    result: &mut Vec<InlayHint>,
+) {
+    for node in module {
+        let NodeId::Expr(expr_id) = node else {
+            continue;
+        };
+        let IrExprEnum::Whr { assignments, .. } = &module.get_expr(expr_id).value else {
+            continue;
+        };
+        for (_, _, identifier_loc, value) in assignments {
+            if let Ok(sort) = query_resolved_sort(context, *value) {
+                result.push(create_hint(
+                    end_position(*identifier_loc),
+                    format!(": {}", ResolvedSortDisplay::new(module, &sort)),
+                    InlayHintKind::TYPE,
+                ));
+            }
+        }
+    }
+}
+
+/// Adds the sorts of variables of binders (e.g. `lambda`) whose sort is not
+/// written down in the source.
+fn add_binder_sort_hints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    result: &mut Vec<InlayHint>,
+) {
+    for node in module {
+        let NodeId::Expr(expr_id) = node else {
+            continue;
+        };
+        let IrExprEnum::Binder { def_id, identifier_loc, .. } = &module.get_expr(expr_id).value else {
+            continue;
+        };
+        let has_inferred_sort = get_token_index(tokens, *identifier_loc)
+            .and_then(|index| tokens.get(index + 1))
+            .is_some_and(|token| !matches!(token.value, LexicalElement::Colon));
+        if !has_inferred_sort {
+            continue;
+        }
+        let DefiningNode::BinderExpr { sort, .. } = get_defining_node_from_def(module, *def_id) else {
+            continue;
+        };
+        if let Ok(sort) = query_resolved_sort(context, sort) {
+            result.push(create_hint(
+                end_position(*identifier_loc),
+                format!(": {}", ResolvedSortDisplay::new(module, &sort)),
+                InlayHintKind::TYPE,
+            ));
+        }
+    }
+}
+
+/// Adds the sorts of the action arguments and conditions that occur inside
+/// the body of a `sum`, leaving out plain variables.
+fn add_sum_expression_sort_hints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    result: &mut Vec<InlayHint>,
+) {
+    for node in module {
+        let NodeId::Expr(expr_id) = node else {
+            continue;
+        };
+        if !matches!(module.get_parent(node), Some(NodeId::Action(_) | NodeId::Proc(_))) {
+            continue;
+        }
+        let expr = module.get_expr(expr_id);
+        if matches!(expr.value, IrExprEnum::Name { .. }) {
+            continue;
+        }
+        let in_sum = ParentIterator::new(module, node).any(|ancestor| match ancestor {
+            NodeId::Proc(proc_id) => {
+                matches!(module.get_proc(proc_id).value, IrProcEnum::Sum { .. })
+            },
+            _ => false,
+        });
+        if !in_sum {
+            continue;
+        }
+        if let Ok(sort) = query_resolved_sort(context, expr_id) {
+            result.push(create_hint(
+                end_position(expr.loc),
+                format!(": {}", ResolvedSortDisplay::new(module, &sort)),
+                InlayHintKind::TYPE,
+            ));
+        }
+    }
+}
+
+fn create_hint(position: Position, label: String, kind: InlayHintKind) -> InlayHint {
+    InlayHint {
+        position,
+        label: InlayHintLabel::String(label),
+        kind: Some(kind),
+        text_edits: None,
+        tooltip: None,
+        padding_left: None,
+        padding_right: Some(kind == InlayHintKind::PARAMETER),
+        data: None,
+    }
+}
+
+fn start_position(loc: SourceRange) -> Position {
+    Position::new(loc.get_start_line(), loc.get_start_char())
+}
+
+fn end_position(loc: SourceRange) -> Position {
+    Position::new(loc.get_end_line(), loc.get_end_char())
+}
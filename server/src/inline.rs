@@ -0,0 +1,462 @@
+use crate::source_mapping::{
+    get_head_symbol, get_identifier_node_at_loc, get_references, IdentifierIterator,
+};
+use crate::struct_sort::get_struct_constructors;
+use crate::util::{
+    get_byte_offset, get_source_slice, get_token_index, is_section_keyword, range_contains,
+    range_equals, source_range_to_lsp_range,
+};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::{SourceCursorPos, SourceRange};
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId, SortId};
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// Returns the code action that inlines the map or sort alias whose name is at
+/// `loc`, at all places where it is used.
+///
+/// A map can only be inlined if it is not recursive and is defined by a
+/// single unconditional equation whose arguments are distinct variables,
+/// such as `f(x, y) = x + y`, and if it is always applied to all arguments.
+/// The declaration is removed, as well as the equation of a map, unless it
+/// shares its declaration with other names (as in `map f, g: Nat;`).
+pub fn get_inline_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    uri: &Url,
+    loc: SourceRange,
+) -> Vec<CodeAction> {
+    let cursor = SourceCursorPos::new(loc.get_start_line(), loc.get_start_char());
+    let Some((_, node, def_id)) = get_identifier_node_at_loc(module, cursor) else {
+        return Vec::new()
+    };
+    let def_id = match def_id {
+        Some(def_id) => def_id,
+        None => match query_def_of_name(context, node) {
+            Ok(def_id) => def_id,
+            Err(()) => return Vec::new(),
+        },
+    };
+    let DefiningNode::Decl(decl) = get_defining_node_from_def(module, def_id) else {
+        return Vec::new()
+    };
+
+    let decl_loc = decl.identifier_loc;
+    let (kind, edits) = match decl.value {
+        IrDeclEnum::Map { .. } => {
+            ("map", get_inline_map_edits(context, module, tokens, input, def_id, decl_loc))
+        },
+        IrDeclEnum::SortAlias { sort } if get_struct_constructors(module, def_id).is_empty() => {
+            let edits = get_inline_sort_edits(
+                context,
+                module,
+                tokens,
+                input,
+                def_id,
+                decl_loc,
+                sort,
+            );
+            ("sort", edits)
+        },
+        _ => return Vec::new(),
+    };
+    let Some(edits) = edits else {
+        return Vec::new()
+    };
+    vec![CodeAction {
+        title: format!("Inline {} `{}`", kind, decl.identifier),
+        kind: Some(CodeActionKind::REFACTOR_INLINE),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        ..CodeAction::default()
+    }]
+}
+
+fn get_inline_map_edits(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    def_id: DefId,
+    decl_loc: SourceRange,
+) -> Option<Vec<TextEdit>> {
+    // find the single equation of the map
+    let mut rules = module.into_iter().filter_map(|node| match node {
+        NodeId::RewriteRule(rule_id) => {
+            let rule = module.get_rewrite_rule(rule_id);
+            let head = get_head_symbol(module, rule.lhs)?;
+            let is_map = query_def_of_name(context, NodeId::Expr(head)) == Ok(def_id);
+            is_map.then_some((node, rule))
+        },
+        _ => None,
+    });
+    let (rule_node, rule) = rules.next()?;
+    if rules.next().is_some() || rule.condition.is_some() {
+        return None;
+    }
+    let rule_loc = module.get_node_loc(rule_node);
+
+    // the arguments of the left-hand side must be distinct variables
+    let params = match &module.get_expr(rule.lhs).value {
+        IrExprEnum::Name { .. } => Vec::new(),
+        IrExprEnum::Apply { callee, args }
+            if matches!(module.get_expr(*callee).value, IrExprEnum::Name { .. }) =>
+        {
+            let mut params = Vec::new();
+            for &arg in args {
+                if !matches!(module.get_expr(arg).value, IrExprEnum::Name { .. }) {
+                    return None;
+                }
+                let param = query_def_of_name(context, NodeId::Expr(arg)).ok()?;
+                let is_variable = matches!(
+                    get_defining_node_from_def(module, param),
+                    DefiningNode::RewriteVar(_),
+                );
+                if !is_variable || params.contains(&param) {
+                    return None;
+                }
+                params.push(param);
+            }
+            params
+        },
+        _ => return None,
+    };
+
+    // find the parameters in the right-hand side, and make sure that the map
+    // is not recursive
+    let rhs_loc = module.get_expr(rule.rhs).loc;
+    let mut param_uses = Vec::new();
+    for (_, loc, node, is_def) in IdentifierIterator::new(module, NodeId::Expr(rule.rhs)) {
+        if is_def.is_some() {
+            continue;
+        }
+        let Ok(target) = query_def_of_name(context, node) else {
+            continue;
+        };
+        if target == def_id {
+            return None;
+        }
+        if let Some(index) = params.iter().position(|&param| param == target) {
+            param_uses.push((loc, index));
+        }
+    }
+    let substitute = |args: &[String]| {
+        substitute_in_source(input, rhs_loc, &param_uses, |index| parenthesize(&args[index]))
+    };
+
+    let decl_node = module.get_def_source(def_id);
+    let mut uses = Vec::new();
+    for loc in get_references(context, module, decl_node).ok()? {
+        if range_contains(rule_loc, loc) {
+            continue;
+        }
+        let name_expr = find_name_expr(module, loc)?;
+        if params.is_empty() {
+            uses.push(MapUse { loc, args: Vec::new() });
+            continue;
+        }
+        let Some(NodeId::Expr(call)) = module.get_parent(NodeId::Expr(name_expr)) else {
+            return None
+        };
+        let IrExprEnum::Apply { callee, args } = &module.get_expr(call).value else {
+            return None
+        };
+        if *callee != name_expr || args.len() != params.len() {
+            return None;
+        }
+        uses.push(MapUse {
+            loc: module.get_expr(call).loc,
+            args: args.iter().map(|&arg| module.get_expr(arg).loc).collect(),
+        });
+    }
+    let mut edits = get_use_edits(input, &uses, &substitute);
+
+    let lhs_loc = module.get_expr(rule.lhs).loc;
+    let deletions = get_deletion_range(tokens, decl_loc, Some(LexicalElement::Colon))
+        .zip(get_deletion_range(tokens, lhs_loc, None));
+    if let Some((decl_range, rule_range)) = deletions {
+        edits.push(TextEdit::new(decl_range, String::new()));
+        edits.push(TextEdit::new(rule_range, String::new()));
+    }
+    Some(edits)
+}
+
+fn get_inline_sort_edits(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    def_id: DefId,
+    decl_loc: SourceRange,
+    sort: SortId,
+) -> Option<Vec<TextEdit>> {
+    let target = get_source_slice(input, module.get_sort(sort).loc);
+    if target.is_empty() {
+        return None;
+    }
+    let target = if target.contains("->") {
+        format!("({})", target)
+    } else {
+        target.to_owned()
+    };
+
+    let decl_node = module.get_def_source(def_id);
+    let mut edits = get_references(context, module, decl_node).ok()?
+        .into_iter()
+        .map(|loc| TextEdit::new(source_range_to_lsp_range(loc), target.clone()))
+        .collect::<Vec<_>>();
+    let deletion = get_deletion_range(tokens, decl_loc, Some(LexicalElement::Equals))?;
+    edits.push(TextEdit::new(deletion, String::new()));
+    Some(edits)
+}
+
+/// A use of the inlined map, which is either a call or, for a map without
+/// parameters, just its name.
+struct MapUse {
+    loc: SourceRange,
+    args: Vec<SourceRange>,
+}
+
+/// Returns the edits that replace the uses of a map by its right-hand side,
+/// which `substitute` returns for the given arguments.
+///
+/// Uses inside the arguments of other uses, as in `f(f(x))`, are inlined into
+/// the text of the outer use, so that there is one edit for each outermost
+/// use and the edits do not overlap.
+fn get_use_edits(
+    input: &str,
+    uses: &[MapUse],
+    substitute: &dyn Fn(&[String]) -> String,
+) -> Vec<TextEdit> {
+    (0..uses.len())
+        .filter(|&index| {
+            !uses.iter().any(|other| {
+                range_contains(other.loc, uses[index].loc) &&
+                    !range_equals(other.loc, uses[index].loc)
+            })
+        })
+        .map(|index| {
+            let text = get_inlined_use(input, uses, index, substitute);
+            TextEdit::new(source_range_to_lsp_range(uses[index].loc), text)
+        })
+        .collect()
+}
+
+/// Returns the text that replaces the use of a map at `index`, where the uses
+/// inside its arguments are replaced as well.
+fn get_inlined_use(
+    input: &str,
+    uses: &[MapUse],
+    index: usize,
+    substitute: &dyn Fn(&[String]) -> String,
+) -> String {
+    let args = uses[index].args.iter()
+        .map(|&arg_loc| {
+            // nested uses are skipped by `substitute_in_source`, since they
+            // overlap with the uses around them
+            let inner = uses.iter()
+                .enumerate()
+                .filter(|(_, inner)| range_contains(arg_loc, inner.loc))
+                .map(|(inner_index, inner)| (inner.loc, inner_index))
+                .collect::<Vec<_>>();
+            substitute_in_source(input, arg_loc, &inner, |inner_index| {
+                get_inlined_use(input, uses, inner_index, substitute)
+            })
+        })
+        .collect::<Vec<_>>();
+    parenthesize(&substitute(&args))
+}
+
+/// Returns the name expression at exactly `loc`.
+fn find_name_expr(module: &IrModule, loc: SourceRange) -> Option<ExprId> {
+    module.into_iter().find_map(|node| match node {
+        NodeId::Expr(expr_id) if range_equals(module.get_node_loc(node), loc) => {
+            let is_name = matches!(module.get_expr(expr_id).value, IrExprEnum::Name { .. });
+            is_name.then_some(expr_id)
+        },
+        _ => None,
+    })
+}
+
+/// Returns the source at `loc`, where each of the given locations inside it
+/// is replaced by the text for the corresponding index.
+fn substitute_in_source(
+    input: &str,
+    loc: SourceRange,
+    replacements: &[(SourceRange, usize)],
+    get_text: impl Fn(usize) -> String,
+) -> String {
+    let source = get_source_slice(input, loc);
+    let Some(start) = get_byte_offset(input, loc.get_start_line(), loc.get_start_char()) else {
+        return source.to_owned()
+    };
+    let mut replacements = replacements.iter()
+        .filter_map(|&(range, index)| {
+            let range_start =
+                get_byte_offset(input, range.get_start_line(), range.get_start_char())?;
+            let range_end = get_byte_offset(input, range.get_end_line(), range.get_end_char())?;
+            Some((range_start.checked_sub(start)?, range_end.checked_sub(start)?, index))
+        })
+        .collect::<Vec<_>>();
+    replacements.sort();
+
+    let mut result = String::new();
+    let mut current = 0;
+    for (range_start, range_end, index) in replacements {
+        if range_start < current || range_end > source.len() {
+            continue;
+        }
+        result.push_str(&source[current..range_start]);
+        result.push_str(&get_text(index));
+        current = range_end;
+    }
+    result.push_str(&source[current..]);
+    result
+}
+
+/// Surrounds an expression by parentheses, unless it is a name that is
+/// possibly applied to arguments, such as `x` or `f(x, y)`.
+fn parenthesize(text: &str) -> String {
+    let rest = text.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '\'');
+    let is_atomic = !text.is_empty() && (rest.is_empty() || rest.starts_with('(') && {
+        // the opening parenthesis must be closed at the very end
+        let mut depth = 0usize;
+        let closing = rest.char_indices().find(|&(_, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {},
+            }
+            depth == 0
+        });
+        closing.is_some_and(|(index, _)| index == rest.len() - 1)
+    });
+    if is_atomic {
+        text.to_owned()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Returns the range to delete to remove the declaration or equation that
+/// starts at `loc`, including the section keyword if it is the only one in
+/// its section, or `None` if it cannot be removed on its own.
+///
+/// `separator` is the token that must follow the first token of a
+/// declaration, such as the `:` of a map declaration, to make sure that it
+/// does not declare other names as well.
+fn get_deletion_range(
+    tokens: &[Token],
+    loc: SourceRange,
+    separator: Option<LexicalElement>,
+) -> Option<Range> {
+    use LexicalElement::*;
+
+    let start = get_token_index(tokens, loc)?;
+    if let Some(separator) = separator {
+        let next = tokens.get(start + 1)?;
+        if std::mem::discriminant(&next.value) != std::mem::discriminant(&separator) {
+            return None;
+        }
+    }
+    if start == 0 {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut end = start;
+    loop {
+        match tokens.get(end)?.value {
+            OpeningParen | OpeningBracket | OpeningBrace => depth += 1,
+            ClosingParen | ClosingBracket | ClosingBrace => depth = depth.saturating_sub(1),
+            Semicolon if depth == 0 => break,
+            _ => {},
+        }
+        end += 1;
+    }
+
+    let previous = &tokens[start - 1];
+    let following = tokens.get(end + 1);
+    let is_first = is_section_keyword(&previous.value);
+    let is_last = following.map_or(true, |token| is_section_keyword(&token.value));
+
+    // an `eqn` section that is removed takes the `var` section before it
+    // along, since its variables can only be used by its equations
+    let mut section_start = previous;
+    if is_first && is_last && matches!(previous.value, Eqn) {
+        let previous_section = tokens[..start - 1].iter()
+            .rev()
+            .find(|token| is_section_keyword(&token.value));
+        if let Some(var) = previous_section.filter(|token| matches!(token.value, Var)) {
+            section_start = var;
+        }
+    }
+    let start_of = |token: &Token| {
+        Position::new(token.loc.get_start_line(), token.loc.get_start_char())
+    };
+    let end_of = |token: &Token| Position::new(token.loc.get_end_line(), token.loc.get_end_char());
+    let range = match following {
+        // remove the whole section, up to the next one
+        Some(following) if is_first && is_last => {
+            Range::new(start_of(section_start), start_of(following))
+        },
+        // remove up to the next declaration in the section
+        Some(following) if !is_last => Range::new(start_of(&tokens[start]), start_of(following)),
+        None if is_first => Range::new(start_of(section_start), end_of(&tokens[end])),
+        // remove from the end of the previous declaration in the section
+        _ => Range::new(end_of(previous), end_of(&tokens[end])),
+    };
+    Some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parenthesize_keeps_names_and_applications() {
+        assert_eq!(parenthesize("x"), "x");
+        assert_eq!(parenthesize("x'"), "x'");
+        assert_eq!(parenthesize("f(x, g(y))"), "f(x, g(y))");
+    }
+
+    #[test]
+    fn parenthesize_surrounds_other_expressions() {
+        assert_eq!(parenthesize("x + 1"), "(x + 1)");
+        assert_eq!(parenthesize("f(x) + g(y)"), "(f(x) + g(y))");
+        assert_eq!(parenthesize("lambda x: Nat . x"), "(lambda x: Nat . x)");
+    }
+
+    #[test]
+    fn nested_uses_are_inlined_into_one_edit() {
+        // the uses of `f(x) = x + 1` in `g(f(f(y)), f(z))`
+        let input = "g(f(f(y)), f(z))";
+        let map_use = |start, end| MapUse {
+            loc: SourceRange::new(0, start, 0, end),
+            args: vec![SourceRange::new(0, start + 2, 0, end - 1)],
+        };
+        let uses = [map_use(2, 9), map_use(4, 8), map_use(11, 15)];
+        let substitute = |args: &[String]| format!("{} + 1", parenthesize(&args[0]));
+        let edits = get_use_edits(input, &uses, &substitute);
+        assert_eq!(edits, vec![
+            TextEdit::new(
+                Range::new(Position::new(0, 2), Position::new(0, 9)),
+                "((y + 1) + 1)".to_owned(),
+            ),
+            TextEdit::new(
+                Range::new(Position::new(0, 11), Position::new(0, 15)),
+                "(z + 1)".to_owned(),
+            ),
+        ]);
+    }
+}
@@ -1,7 +1,23 @@
-
+pub mod builtin;
 pub mod core;
 pub mod def_info;
+pub mod diagnostic_code;
+pub mod documentation;
+pub mod extract_map;
+pub mod extract_process;
+pub mod folding_range;
+pub mod formatting;
+pub mod inlay_hint;
+pub mod inline;
+pub mod lint;
 pub mod lsp_context;
+pub mod quick_fix;
+pub mod section_order;
 pub mod semantic_token;
+pub mod signature_change;
+pub mod signature_help;
 pub mod source_mapping;
+pub mod struct_generation;
+pub mod struct_sort;
+pub mod suppression;
 pub mod util;
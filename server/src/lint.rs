@@ -0,0 +1,131 @@
+pub mod communication;
+pub mod exhaustiveness;
+pub mod reachability;
+pub mod recursion;
+pub mod rewrite;
+pub mod shadowing;
+pub mod unused;
+
+use crate::lint::communication::ActionRule;
+use crate::util::range_contains;
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::core::lexer::Token;
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::module::IrModule;
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use std::collections::HashSet;
+
+/// A single warning (or hint) that is reported by one of the lints.
+pub struct Lint {
+    pub kind: LintKind,
+    pub loc: SourceRange,
+    pub message: String,
+    /// Other locations that help explain the lint, each with a message.
+    pub related: Vec<(SourceRange, String)>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LintKind {
+    Unused,
+    Shadowing,
+    UnboundRewriteVariable,
+    ConstructorHead,
+    DuplicateEquation,
+    NonExhaustive,
+    OverlappingEquations,
+    UnguardedRecursion,
+    InvalidActionRule,
+    UnproducedAction,
+    ImpossibleMultiAction,
+    Unreachable,
+}
+
+impl LintKind {
+    pub fn get_severity(&self) -> DiagnosticSeverity {
+        match self {
+            LintKind::Unused => DiagnosticSeverity::WARNING,
+            LintKind::Shadowing => DiagnosticSeverity::WARNING,
+            LintKind::UnboundRewriteVariable => DiagnosticSeverity::WARNING,
+            LintKind::ConstructorHead => DiagnosticSeverity::WARNING,
+            LintKind::DuplicateEquation => DiagnosticSeverity::WARNING,
+            LintKind::NonExhaustive => DiagnosticSeverity::WARNING,
+            LintKind::OverlappingEquations => DiagnosticSeverity::INFORMATION,
+            LintKind::UnguardedRecursion => DiagnosticSeverity::ERROR,
+            LintKind::InvalidActionRule => DiagnosticSeverity::WARNING,
+            LintKind::UnproducedAction => DiagnosticSeverity::WARNING,
+            LintKind::ImpossibleMultiAction => DiagnosticSeverity::WARNING,
+            LintKind::Unreachable => DiagnosticSeverity::HINT,
+        }
+    }
+
+    /// Returns whether the code that is reported can be removed without
+    /// changing the model, so that editors can fade it out.
+    pub fn is_unnecessary(&self) -> bool {
+        match self {
+            LintKind::Unused => true,
+            LintKind::Shadowing => false,
+            LintKind::UnboundRewriteVariable => false,
+            LintKind::ConstructorHead => false,
+            LintKind::DuplicateEquation => true,
+            LintKind::NonExhaustive => false,
+            LintKind::OverlappingEquations => false,
+            LintKind::UnguardedRecursion => false,
+            LintKind::InvalidActionRule => false,
+            LintKind::UnproducedAction => false,
+            LintKind::ImpossibleMultiAction => false,
+            LintKind::Unreachable => true,
+        }
+    }
+}
+
+/// Runs all lints on the given module.
+///
+/// A lint checks for parts of a model that are valid, but most likely not
+/// what the user intended, such as declarations that are never used.
+pub fn get_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+) -> Vec<Lint> {
+    let mut result = Vec::new();
+    result.extend(unused::get_unused_lints(context, module));
+    result.extend(shadowing::get_shadowing_lints(module));
+    result.extend(rewrite::get_rewrite_lints(context, module, tokens, input));
+    result.extend(exhaustiveness::get_exhaustiveness_lints(context, module, tokens, input));
+    result.extend(recursion::get_recursion_lints(context, module));
+    result.extend(communication::get_communication_lints(context, module, tokens));
+    result.extend(reachability::get_reachability_lints(context, module, tokens));
+    result
+}
+
+/// Applies the rules of the `comm` or `rename` operator at `operator_loc` to
+/// the names of the actions that its operand (at `operand_loc`) can perform.
+///
+/// A rule applies if all actions on its left-hand side can be performed. The
+/// actions of a `rename` rule are replaced by its target, while those of a
+/// `comm` rule can still be performed on their own. The rules of operators
+/// inside the operand are skipped, since they were applied already.
+pub fn apply_action_rules(
+    rules: &[ActionRule],
+    operator_loc: SourceRange,
+    operand_loc: SourceRange,
+    actions: &mut HashSet<String>,
+) {
+    for rule in rules {
+        let is_own_rule = range_contains(operator_loc, rule.keyword_loc) &&
+            !range_contains(operand_loc, rule.keyword_loc);
+        if !is_own_rule || !rule.lhs.iter().all(|(name, _)| actions.contains(name)) {
+            continue;
+        }
+        if !rule.is_comm {
+            for (name, _) in &rule.lhs {
+                actions.remove(name);
+            }
+        }
+        actions.insert(rule.target.0.clone());
+    }
+}
@@ -0,0 +1,388 @@
+use crate::lint::{apply_action_rules, Lint, LintKind};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{ActionId, IrModule, NodeId, ProcId, SortId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use std::collections::{HashMap, HashSet};
+
+/// A rule in the set of a `comm` or `rename` operator, such as `a | b -> c`.
+///
+/// Since the IR does not keep these sets, they are read from the tokens.
+pub struct ActionRule {
+    /// The location of the `comm` or `rename` keyword.
+    pub keyword_loc: SourceRange,
+    pub is_comm: bool,
+    pub lhs: Vec<(String, SourceRange)>,
+    pub target: (String, SourceRange),
+}
+
+/// The actions that a process can perform.
+#[derive(Clone, Default, Eq, PartialEq)]
+struct ProducedActions {
+    names: HashSet<String>,
+    /// Whether processes are composed in parallel somewhere, so that
+    /// multi-actions can occur.
+    has_parallelism: bool,
+}
+
+/// Reports inconsistencies in the action operators of process expressions,
+/// namely:
+/// - `comm` rules whose actions have different parameter sorts, and `comm`
+///   and `rename` rules that refer to names that are not declared actions
+/// - actions in `allow`, `block` and `hide` that are never performed by the
+///   processes that these operators are applied to
+/// - multi-actions in `allow` that can never occur, because the processes are
+///   not composed in parallel
+///
+/// The processes that are called are followed, and the actions that are
+/// created by `comm` and `rename` are taken into account.
+pub fn get_communication_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+) -> Vec<Lint> {
+    let rules = parse_action_rules(tokens);
+    let mut result = Vec::new();
+    add_action_rule_lints(context, module, &rules, &mut result);
+
+    let process_actions = get_process_actions(context, module, &rules);
+
+    for node in module {
+        let NodeId::Proc(proc_id) = node else {
+            continue;
+        };
+        let (operator, inner, multi_ids) = match &module.get_proc(proc_id).value {
+            IrProcEnum::Allow { multi_ids, proc } => ("allow", *proc, multi_ids.clone()),
+            IrProcEnum::Block { ids, proc } => {
+                ("block", *proc, ids.iter().map(|&id| vec![id]).collect())
+            },
+            IrProcEnum::Hide { ids, proc } => {
+                ("hide", *proc, ids.iter().map(|&id| vec![id]).collect())
+            },
+            _ => continue,
+        };
+
+        let mut produced = ProducedActions::default();
+        add_produced_actions(context, module, &rules, inner, &process_actions, &mut produced);
+        for multi_action in multi_ids {
+            add_multi_action_lints(module, operator, &multi_action, &produced, &mut result);
+        }
+    }
+    result
+}
+
+fn add_action_rule_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    rules: &[ActionRule],
+    result: &mut Vec<Lint>,
+) {
+    // actions can be overloaded, so every name has a list of parameter sorts
+    let mut action_sorts: HashMap<String, Vec<&[SortId]>> = HashMap::new();
+    for node in module {
+        let NodeId::Decl(decl_id) = node else {
+            continue;
+        };
+        let decl = module.get_decl(decl_id);
+        if let IrDeclEnum::Action { params } = &decl.value {
+            action_sorts.entry(decl.identifier.to_string()).or_default().push(params);
+        }
+    }
+    let get_sorts = |name: &str| -> Option<Vec<Vec<String>>> {
+        action_sorts.get(name)?.iter()
+            .map(|params| {
+                params.iter()
+                    .map(|&sort| {
+                        let sort = query_resolved_sort(context, sort).ok()?;
+                        Some(ResolvedSortDisplay::new(module, &sort).to_string())
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let format_sorts = |sorts: &[Vec<String>]| {
+        sorts.iter()
+            .map(|sorts| format!("({})", sorts.join(", ")))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    };
+
+    for rule in rules {
+        let mut first: Option<(&String, SourceRange, Vec<Vec<String>>)> = None;
+        for (name, loc) in rule.lhs.iter().chain(std::iter::once(&rule.target)) {
+            if !action_sorts.contains_key(name) {
+                result.push(Lint {
+                    kind: LintKind::InvalidActionRule,
+                    loc: *loc,
+                    message: format!("`{}` is not a declared action", name),
+                    related: Vec::new(),
+                });
+                continue;
+            }
+            let Some(sorts) = get_sorts(name) else {
+                continue;
+            };
+            match &first {
+                None => first = Some((name, *loc, sorts)),
+                Some((first_name, first_loc, first_sorts))
+                    if !sorts.iter().any(|sorts| first_sorts.contains(sorts)) =>
+                {
+                    result.push(Lint {
+                        kind: LintKind::InvalidActionRule,
+                        loc: *loc,
+                        message: format!(
+                            "action `{}` has parameter sorts {}, but `{}` has {}, \
+                                so they cannot be {}",
+                            name,
+                            format_sorts(&sorts),
+                            first_name,
+                            format_sorts(first_sorts),
+                            if rule.is_comm { "communicated" } else { "renamed" },
+                        ),
+                        related: vec![(*first_loc, format!("`{}` is used here", first_name))],
+                    });
+                },
+                Some(_) => {},
+            }
+        }
+    }
+}
+
+fn add_multi_action_lints(
+    module: &IrModule,
+    operator: &str,
+    multi_action: &[ActionId],
+    produced: &ProducedActions,
+    result: &mut Vec<Lint>,
+) {
+    let (Some(&first), Some(&last)) = (multi_action.first(), multi_action.last()) else {
+        return
+    };
+    let first_loc = module.get_action(first).identifier_loc;
+    let last_loc = module.get_action(last).identifier_loc;
+    let loc = SourceRange::new(
+        first_loc.get_start_line(),
+        first_loc.get_start_char(),
+        last_loc.get_end_line(),
+        last_loc.get_end_char(),
+    );
+    let names = multi_action.iter()
+        .map(|&id| module.get_action(id).identifier.to_string())
+        .collect::<Vec<_>>();
+    let missing = names.iter()
+        .filter(|name| !produced.names.contains(*name))
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>();
+
+    if multi_action.len() == 1 {
+        if !missing.is_empty() {
+            result.push(Lint {
+                kind: LintKind::UnproducedAction,
+                loc,
+                message: format!(
+                    "action `{}` is never performed by the processes in this `{}`",
+                    names[0],
+                    operator,
+                ),
+                related: Vec::new(),
+            });
+        }
+    } else if !missing.is_empty() {
+        result.push(Lint {
+            kind: LintKind::ImpossibleMultiAction,
+            loc,
+            message: format!(
+                "multi-action `{}` can never occur, since {} {} never performed",
+                names.join("|"),
+                missing.join(", "),
+                if missing.len() == 1 { "is" } else { "are" },
+            ),
+            related: Vec::new(),
+        });
+    } else if !produced.has_parallelism {
+        result.push(Lint {
+            kind: LintKind::ImpossibleMultiAction,
+            loc,
+            message: format!(
+                "multi-action `{}` can never occur, since no processes are composed in parallel",
+                names.join("|"),
+            ),
+            related: Vec::new(),
+        });
+    }
+}
+
+/// Returns the actions that each declared process can perform.
+///
+/// Since processes can call each other recursively, the bodies of all
+/// processes are evaluated again until none of the results change.
+fn get_process_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    rules: &[ActionRule],
+) -> HashMap<DefId, ProducedActions> {
+    let processes = module.into_iter()
+        .filter_map(|node| match node {
+            NodeId::Decl(decl_id) => {
+                let decl = module.get_decl(decl_id);
+                match &decl.value {
+                    IrDeclEnum::Process { proc, .. } => Some((decl.def_id, *proc)),
+                    _ => None,
+                }
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = HashMap::new();
+    loop {
+        let mut is_changed = false;
+        for &(def_id, body) in &processes {
+            let mut produced = ProducedActions::default();
+            add_produced_actions(context, module, rules, body, &result, &mut produced);
+            if result.get(&def_id) != Some(&produced) {
+                result.insert(def_id, produced);
+                is_changed = true;
+            }
+        }
+        if !is_changed {
+            return result;
+        }
+    }
+}
+
+/// Adds the actions that `proc` can perform, where the actions of the
+/// processes that it calls are taken from `process_actions`.
+fn add_produced_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    rules: &[ActionRule],
+    proc: ProcId,
+    process_actions: &HashMap<DefId, ProducedActions>,
+    result: &mut ProducedActions,
+) {
+    let proc_value = module.get_proc(proc);
+    match &proc_value.value {
+        IrProcEnum::Action { value } => {
+            let def_id = query_def_of_name(context, NodeId::Action(*value)).ok();
+            let is_process = def_id.is_some_and(|def_id| {
+                match get_defining_node_from_def(module, def_id) {
+                    DefiningNode::Decl(decl) => matches!(decl.value, IrDeclEnum::Process { .. }),
+                    _ => false,
+                }
+            });
+            if !is_process {
+                result.names.insert(module.get_action(*value).identifier.to_string());
+            } else if let Some(called) = def_id.and_then(|def_id| process_actions.get(&def_id)) {
+                result.names.extend(called.names.iter().cloned());
+                result.has_parallelism |= called.has_parallelism;
+            }
+        },
+        IrProcEnum::Multi { lhs, rhs } |
+        IrProcEnum::Parallel { lhs, rhs } |
+        IrProcEnum::LeftParallel { lhs, rhs } => {
+            result.has_parallelism = true;
+            add_produced_actions(context, module, rules, *lhs, process_actions, result);
+            add_produced_actions(context, module, rules, *rhs, process_actions, result);
+        },
+        IrProcEnum::Add { lhs, rhs } |
+        IrProcEnum::Concat { lhs, rhs } => {
+            add_produced_actions(context, module, rules, *lhs, process_actions, result);
+            add_produced_actions(context, module, rules, *rhs, process_actions, result);
+        },
+        IrProcEnum::IfThen { then_proc, .. } => {
+            add_produced_actions(context, module, rules, *then_proc, process_actions, result);
+        },
+        IrProcEnum::IfThenElse { then_proc, else_proc, .. } => {
+            add_produced_actions(context, module, rules, *then_proc, process_actions, result);
+            add_produced_actions(context, module, rules, *else_proc, process_actions, result);
+        },
+        IrProcEnum::Comm { proc } | IrProcEnum::Rename { proc } => {
+            // the rules only apply to the actions of this operand
+            let mut inner = ProducedActions::default();
+            add_produced_actions(context, module, rules, *proc, process_actions, &mut inner);
+            let operand_loc = module.get_proc(*proc).loc;
+            apply_action_rules(rules, proc_value.loc, operand_loc, &mut inner.names);
+            result.names.extend(inner.names);
+            result.has_parallelism |= inner.has_parallelism;
+        },
+        IrProcEnum::Allow { proc, .. } |
+        IrProcEnum::Block { proc, .. } |
+        IrProcEnum::Hide { proc, .. } |
+        IrProcEnum::Sum { proc, .. } => {
+            add_produced_actions(context, module, rules, *proc, process_actions, result);
+        },
+        IrProcEnum::Delta | IrProcEnum::Tau => {},
+    }
+}
+
+/// Reads the rules of all `comm` and `rename` sets, such as
+/// `comm({a | b -> c}, P)` and `rename({a -> b}, P)`, stopping at the first
+/// rule of a set that cannot be parsed.
+pub fn parse_action_rules(tokens: &[Token]) -> Vec<ActionRule> {
+    use LexicalElement::*;
+
+    let mut result = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let is_comm = match token.value {
+            Comm => true,
+            Rename => false,
+            _ => continue,
+        };
+        let (Some(OpeningParen), Some(OpeningBrace)) = (
+            tokens.get(index + 1).map(|token| &token.value),
+            tokens.get(index + 2).map(|token| &token.value),
+        ) else {
+            continue;
+        };
+
+        let mut current = index + 3;
+        while let Some(rule) = parse_action_rule(tokens, &mut current, token.loc, is_comm) {
+            result.push(rule);
+            match tokens.get(current).map(|token| &token.value) {
+                Some(Comma) => current += 1,
+                _ => break,
+            }
+        }
+    }
+    result
+}
+
+/// Parses a single rule such as `a | b -> c`, starting at token `current` and
+/// leaving `current` at the token after the rule.
+fn parse_action_rule(
+    tokens: &[Token],
+    current: &mut usize,
+    keyword_loc: SourceRange,
+    is_comm: bool,
+) -> Option<ActionRule> {
+    use LexicalElement::*;
+
+    let mut lhs = Vec::new();
+    loop {
+        let Identifier(name) = &tokens.get(*current)?.value else {
+            return None
+        };
+        lhs.push((name.clone(), tokens[*current].loc));
+        *current += 2;
+        match tokens.get(*current - 1)?.value {
+            Pipe => {},
+            Arrow => break,
+            _ => return None,
+        }
+    }
+    let Identifier(name) = &tokens.get(*current)?.value else {
+        return None
+    };
+    let target = (name.clone(), tokens[*current].loc);
+    *current += 1;
+    Some(ActionRule { keyword_loc, is_comm, lhs, target })
+}
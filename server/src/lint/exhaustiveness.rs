@@ -0,0 +1,207 @@
+use crate::lint::{Lint, LintKind};
+use crate::lint::rewrite::get_normalized_text;
+use crate::source_mapping::get_head_symbol;
+use crate::struct_sort::{get_struct_constructors, get_struct_sort_of_constructor};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::core::lexer::Token;
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::iterator::{DefiningNode, get_def_data, get_defining_node_from_def};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId};
+
+use std::collections::HashSet;
+
+/// The pattern of an argument in the left-hand side of an equation.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Pattern {
+    /// A variable, which matches every term.
+    Any,
+    /// A constructor without arguments.
+    Constructor(DefId),
+}
+
+struct Equation {
+    loc: SourceRange,
+    /// The normalized text of the equation, to leave exact duplicates to the
+    /// lint for duplicate equations.
+    text: String,
+    patterns: Vec<Pattern>,
+    is_conditional: bool,
+}
+
+/// Reports maps that are defined by pattern matching on the constructors of
+/// a struct sort, but that have no equation for some of the constructors.
+/// Also notes unconditional equations that overlap, i.e. that can both be
+/// applied to the same term.
+///
+/// Only maps whose equations have arguments that are variables or
+/// constructors without arguments are checked, since nested patterns such as
+/// `f(c(d))` only cover part of a constructor. Conditional equations are
+/// assumed to cover their patterns.
+pub fn get_exhaustiveness_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+) -> Vec<Lint> {
+    let mut maps: Vec<(DefId, Vec<Equation>)> = Vec::new();
+    let mut skipped_maps = HashSet::new();
+    for node in module {
+        let NodeId::RewriteRule(rule_id) = node else {
+            continue;
+        };
+        let rule = module.get_rewrite_rule(rule_id);
+        let Some((map, args)) = get_map_application(context, module, rule.lhs) else {
+            continue;
+        };
+        let patterns = args.iter()
+            .map(|&arg| get_pattern(context, module, arg))
+            .collect::<Option<Vec<_>>>();
+        let Some(patterns) = patterns else {
+            skipped_maps.insert(map);
+            continue;
+        };
+        let loc = module.get_node_loc(node);
+        let equation = Equation {
+            loc,
+            text: get_normalized_text(tokens, input, loc),
+            patterns,
+            is_conditional: rule.condition.is_some(),
+        };
+        match maps.iter_mut().find(|(def_id, _)| *def_id == map) {
+            Some((_, equations)) => equations.push(equation),
+            None => maps.push((map, vec![equation])),
+        }
+    }
+
+    let mut result = Vec::new();
+    for (map, equations) in maps {
+        if skipped_maps.contains(&map) {
+            continue;
+        }
+        let matches_constructors = equations.iter()
+            .flat_map(|equation| &equation.patterns)
+            .any(|pattern| matches!(pattern, Pattern::Constructor(_)));
+        if !matches_constructors {
+            continue;
+        }
+        let map_source = module.get_def_source(map);
+        let Some((_, map_identifier, map_loc)) = get_def_data(module, map_source) else {
+            continue;
+        };
+
+        let arity = equations.iter().map(|equation| equation.patterns.len()).max().unwrap_or(0);
+        for position in 0..arity {
+            let patterns = equations.iter()
+                .filter_map(|equation| equation.patterns.get(position).copied())
+                .collect::<Vec<_>>();
+            if patterns.contains(&Pattern::Any) {
+                continue;
+            }
+            let Some(sort) = patterns.iter().find_map(|pattern| match pattern {
+                Pattern::Constructor(constructor) => {
+                    get_struct_sort_of_constructor(module, *constructor)
+                },
+                Pattern::Any => None,
+            }) else {
+                continue;
+            };
+
+            let missing = get_struct_constructors(module, sort)
+                .into_iter()
+                .filter(|constructor| !patterns.contains(&Pattern::Constructor(*constructor)))
+                .filter_map(|constructor| {
+                    let source = module.get_def_source(constructor);
+                    let (_, identifier, _) = get_def_data(module, source)?;
+                    Some(format!("`{}`", identifier))
+                })
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                result.push(Lint {
+                    kind: LintKind::NonExhaustive,
+                    loc: map_loc,
+                    message: format!(
+                        "map `{}` has no equation for {} {} in argument {}",
+                        map_identifier,
+                        if missing.len() == 1 { "constructor" } else { "constructors" },
+                        missing.join(", "),
+                        position + 1,
+                    ),
+                    related: Vec::new(),
+                });
+            }
+        }
+
+        for (index, equation) in equations.iter().enumerate() {
+            if equation.is_conditional {
+                continue;
+            }
+            let overlapping = equations[..index].iter().find(|earlier| {
+                !earlier.is_conditional &&
+                    earlier.text != equation.text &&
+                    patterns_overlap(&earlier.patterns, &equation.patterns)
+            });
+            if let Some(earlier) = overlapping {
+                result.push(Lint {
+                    kind: LintKind::OverlappingEquations,
+                    loc: equation.loc,
+                    message: format!(
+                        "this equation overlaps with an earlier equation for `{}`",
+                        map_identifier,
+                    ),
+                    related: vec![(earlier.loc, "earlier equation".to_owned())],
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Returns the map and arguments of a left-hand side such as `f(x, c(y))`,
+/// or `None` if it is not a map that is applied to arguments.
+fn get_map_application<'m>(
+    context: &AnalysisContext,
+    module: &'m IrModule,
+    lhs: ExprId,
+) -> Option<(DefId, &'m [ExprId])> {
+    let IrExprEnum::Apply { callee, args } = &module.get_expr(lhs).value else {
+        return None
+    };
+    if !matches!(module.get_expr(*callee).value, IrExprEnum::Name { .. }) {
+        return None;
+    }
+    let def = query_def_of_name(context, (*callee).into()).ok()?;
+    match get_defining_node_from_def(module, def) {
+        DefiningNode::Decl(decl) if matches!(decl.value, IrDeclEnum::Map { .. }) => {
+            Some((def, args))
+        },
+        _ => None,
+    }
+}
+
+/// Returns the pattern of an argument, or `None` if it is not a variable or a
+/// constructor without arguments.
+fn get_pattern(context: &AnalysisContext, module: &IrModule, arg: ExprId) -> Option<Pattern> {
+    if get_head_symbol(module, arg) != Some(arg) {
+        return None;
+    }
+    let def = query_def_of_name(context, arg.into()).ok()?;
+    match get_defining_node_from_def(module, def) {
+        DefiningNode::RewriteVar(_) => Some(Pattern::Any),
+        DefiningNode::Decl(decl) if matches!(decl.value, IrDeclEnum::Constructor { .. }) => {
+            Some(Pattern::Constructor(def))
+        },
+        _ => None,
+    }
+}
+
+/// Returns whether some term is matched by both lists of patterns.
+fn patterns_overlap(lhs: &[Pattern], rhs: &[Pattern]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|pair| match pair {
+        (Pattern::Any, _) | (_, Pattern::Any) => true,
+        (Pattern::Constructor(a), Pattern::Constructor(b)) => a == b,
+    })
+}
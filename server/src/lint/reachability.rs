@@ -0,0 +1,232 @@
+use crate::lint::{apply_action_rules, Lint, LintKind};
+use crate::lint::communication::{ActionRule, parse_action_rules};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::core::lexer::Token;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId, ProcId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use std::collections::{HashMap, HashSet};
+
+/// Reports processes that are never called when starting from `init`, and
+/// actions that can never be performed by `init` because of the `allow` and
+/// `block` operators around them.
+///
+/// Hidden actions still count as performed, since they happen as `tau`, and
+/// so do the actions that `comm` and `rename` turn into a performed action.
+/// Declarations that are not used at all are left to the lint for unused
+/// declarations, and nothing is reported for a model without `init`.
+pub fn get_reachability_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+) -> Vec<Lint> {
+    let Some(init) = get_init_proc(module) else {
+        return Vec::new()
+    };
+    let rules = parse_action_rules(tokens);
+    let mut reached_processes = HashSet::new();
+    let mut performed = get_performed_actions(
+        context,
+        module,
+        &rules,
+        init,
+        &mut HashMap::new(),
+        &mut reached_processes,
+    );
+    add_rule_sources(&rules, &mut performed);
+
+    // the declarations that are referred to somewhere
+    let mut used = HashSet::new();
+    for node in module {
+        if let NodeId::Action(_) = node {
+            if let Ok(def_id) = query_def_of_name(context, node) {
+                used.insert(def_id);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    for node in module {
+        let NodeId::Decl(decl_id) = node else {
+            continue;
+        };
+        let decl = module.get_decl(decl_id);
+        if !used.contains(&decl.def_id) {
+            continue;
+        }
+        match &decl.value {
+            IrDeclEnum::Process { .. } if !reached_processes.contains(&decl.def_id) => {
+                result.push(Lint {
+                    kind: LintKind::Unreachable,
+                    loc: decl.identifier_loc,
+                    message: format!("process `{}` is never reached from `init`", decl.identifier),
+                    related: Vec::new(),
+                });
+            },
+            IrDeclEnum::Action { .. } if !performed.contains(&decl.identifier.to_string()) => {
+                result.push(Lint {
+                    kind: LintKind::Unreachable,
+                    loc: decl.identifier_loc,
+                    message: format!("action `{}` is never performed by `init`", decl.identifier),
+                    related: Vec::new(),
+                });
+            },
+            _ => {},
+        }
+    }
+    result
+}
+
+/// Returns the process of the `init` section, which is the only process
+/// expression that is not part of a process declaration.
+fn get_init_proc(module: &IrModule) -> Option<ProcId> {
+    module.into_iter().find_map(|node| match node {
+        NodeId::Proc(proc_id) => match module.get_parent(node) {
+            Some(NodeId::Proc(_) | NodeId::Decl(_)) => None,
+            _ => Some(proc_id),
+        },
+        _ => None,
+    })
+}
+
+/// Adds the actions on the left-hand side of each `comm` and `rename` rule
+/// whose target is performed, since these actions are performed as part of
+/// the target, such as `a` and `b` in `allow({c}, comm({a | b -> c}, a || b))`.
+fn add_rule_sources(rules: &[ActionRule], performed: &mut HashSet<String>) {
+    loop {
+        let count = performed.len();
+        for rule in rules {
+            if performed.contains(&rule.target.0) {
+                performed.extend(rule.lhs.iter().map(|(name, _)| name.clone()));
+            }
+        }
+        if performed.len() == count {
+            return;
+        }
+    }
+}
+
+/// Returns the names of the actions that `proc` can perform, and adds the
+/// processes that it calls to `reached_processes`.
+///
+/// `cache` holds the actions of the processes that were already followed
+/// within the current `allow` or `block`, which is `None` for the processes
+/// that are still being followed.
+fn get_performed_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    rules: &[ActionRule],
+    proc: ProcId,
+    cache: &mut HashMap<DefId, Option<HashSet<String>>>,
+    reached_processes: &mut HashSet<DefId>,
+) -> HashSet<String> {
+    let get_inner = |proc, cache: &mut _, reached_processes: &mut _| {
+        get_performed_actions(context, module, rules, proc, cache, reached_processes)
+    };
+
+    let proc_value = module.get_proc(proc);
+    match &proc_value.value {
+        IrProcEnum::Action { value } => {
+            let def_id = query_def_of_name(context, NodeId::Action(*value)).ok();
+            let body = def_id.and_then(|def_id| match get_defining_node_from_def(module, def_id) {
+                DefiningNode::Decl(decl) => match &decl.value {
+                    IrDeclEnum::Process { proc, .. } => Some((def_id, *proc)),
+                    _ => None,
+                },
+                _ => None,
+            });
+            match body {
+                Some((def_id, body)) => {
+                    if let Some(cached) = cache.get(&def_id) {
+                        return cached.clone().unwrap_or_default();
+                    }
+                    reached_processes.insert(def_id);
+                    cache.insert(def_id, None);
+                    let result = get_inner(body, cache, reached_processes);
+                    cache.insert(def_id, Some(result.clone()));
+                    result
+                },
+                None => HashSet::from([module.get_action(*value).identifier.to_string()]),
+            }
+        },
+        IrProcEnum::Add { lhs, rhs } |
+        IrProcEnum::Concat { lhs, rhs } |
+        IrProcEnum::Multi { lhs, rhs } |
+        IrProcEnum::Parallel { lhs, rhs } |
+        IrProcEnum::LeftParallel { lhs, rhs } => {
+            let mut result = get_inner(*lhs, cache, reached_processes);
+            result.extend(get_inner(*rhs, cache, reached_processes));
+            result
+        },
+        IrProcEnum::IfThen { then_proc, .. } => {
+            get_inner(*then_proc, cache, reached_processes)
+        },
+        IrProcEnum::IfThenElse { then_proc, else_proc, .. } => {
+            let mut result = get_inner(*then_proc, cache, reached_processes);
+            result.extend(get_inner(*else_proc, cache, reached_processes));
+            result
+        },
+        IrProcEnum::Comm { proc } | IrProcEnum::Rename { proc } => {
+            let mut result = get_inner(*proc, cache, reached_processes);
+            apply_action_rules(rules, proc_value.loc, module.get_proc(*proc).loc, &mut result);
+            result
+        },
+        IrProcEnum::Allow { multi_ids, proc } => {
+            // processes are followed again, since the actions that they
+            // perform inside this `allow` differ from those outside it
+            let allowed = multi_ids.iter()
+                .flatten()
+                .map(|&id| module.get_action(id).identifier.to_string())
+                .collect::<HashSet<_>>();
+            let mut result = get_inner(*proc, &mut HashMap::new(), reached_processes);
+            result.retain(|name| allowed.contains(name));
+            result
+        },
+        IrProcEnum::Block { ids, proc } => {
+            let blocked = ids.iter()
+                .map(|&id| module.get_action(id).identifier.to_string())
+                .collect::<HashSet<_>>();
+            let mut result = get_inner(*proc, &mut HashMap::new(), reached_processes);
+            result.retain(|name| !blocked.contains(name));
+            result
+        },
+        IrProcEnum::Hide { proc, .. } | IrProcEnum::Sum { proc, .. } => {
+            get_inner(*proc, cache, reached_processes)
+        },
+        IrProcEnum::Delta | IrProcEnum::Tau => HashSet::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nano_crl2::analysis::context::AnalysisContext;
+    use nano_crl2::analysis::parsing::query_token_list;
+
+    fn get_rules(input: &str) -> Vec<ActionRule> {
+        let mut context = AnalysisContext::new();
+        let module_id = context.add_model_input("test.mcrl2".to_owned(), input.to_owned());
+        parse_action_rules(&query_token_list(&context, module_id).unwrap())
+    }
+
+    #[test]
+    fn communicated_actions_are_performed_as_their_target() {
+        let rules = get_rules("act a, b, c;\ninit allow({c}, comm({a | b -> c}, a || b));\n");
+        let mut performed = HashSet::from(["c".to_owned()]);
+        add_rule_sources(&rules, &mut performed);
+        assert_eq!(performed, HashSet::from(["a".to_owned(), "b".to_owned(), "c".to_owned()]));
+    }
+
+    #[test]
+    fn actions_of_unperformed_targets_are_not_performed() {
+        let rules = get_rules("act a, b, c, d;\ninit allow({d}, comm({a | b -> c}, a || d));\n");
+        let mut performed = HashSet::from(["d".to_owned()]);
+        add_rule_sources(&rules, &mut performed);
+        assert_eq!(performed, HashSet::from(["d".to_owned()]));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::lint::{Lint, LintKind};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::core::syntax::{Identifier, SourceRange};
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId, ProcId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use std::collections::{HashMap, VecDeque};
+
+/// A call to a process that can happen before any action is performed.
+struct UnguardedCall {
+    callee: DefId,
+    loc: SourceRange,
+}
+
+/// Reports processes that can call themselves, directly or through other
+/// processes, without first performing an action, such as `P` in
+/// `proc P = P + a . P;`. mCRL2 cannot handle such unguarded recursion.
+///
+/// The call chain of the recursion is given as related information.
+pub fn get_recursion_lints(context: &AnalysisContext, module: &IrModule) -> Vec<Lint> {
+    let mut processes: HashMap<DefId, (&Identifier, ProcId)> = HashMap::new();
+    for node in module {
+        let NodeId::Decl(decl_id) = node else {
+            continue;
+        };
+        let decl = module.get_decl(decl_id);
+        if let IrDeclEnum::Process { proc, .. } = &decl.value {
+            processes.insert(decl.def_id, (&decl.identifier, *proc));
+        }
+    }
+
+    let mut calls: HashMap<DefId, Vec<UnguardedCall>> = HashMap::new();
+    for (&def_id, &(_, proc)) in &processes {
+        let mut result = Vec::new();
+        add_unguarded_calls(context, module, proc, &mut result);
+        calls.insert(def_id, result);
+    }
+
+    let mut result = Vec::new();
+    for (&def_id, &(identifier, _)) in &processes {
+        let Some(chain) = find_cycle(&calls, def_id) else {
+            continue;
+        };
+        let message = if chain.len() == 1 {
+            format!("process `{}` calls itself without performing an action first", identifier)
+        } else {
+            format!(
+                "process `{}` can call itself through {} other {} without performing an action first",
+                identifier,
+                chain.len() - 1,
+                if chain.len() == 2 { "process" } else { "processes" },
+            )
+        };
+        let get_name = |def_id: DefId| {
+            processes.get(&def_id).map_or(String::new(), |(identifier, _)| identifier.to_string())
+        };
+        let related = chain[1..].iter()
+            .map(|(caller, call)| {
+                (call.loc, format!("`{}` calls `{}` here", get_name(*caller), get_name(call.callee)))
+            })
+            .collect();
+        result.push(Lint {
+            kind: LintKind::UnguardedRecursion,
+            loc: chain[0].1.loc,
+            message,
+            related,
+        });
+    }
+    result.sort_by_key(|lint| (lint.loc.get_start_line(), lint.loc.get_start_char()));
+    result
+}
+
+/// Finds the shortest chain of unguarded calls from `start` back to itself,
+/// where each call is paired with the process in which it occurs.
+fn find_cycle(
+    calls: &HashMap<DefId, Vec<UnguardedCall>>,
+    start: DefId,
+) -> Option<Vec<(DefId, &UnguardedCall)>> {
+    // breadth-first search, remembering the call that first reached a process
+    let mut reached_by: HashMap<DefId, (DefId, &UnguardedCall)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for call in calls.get(&current).into_iter().flatten() {
+            if call.callee == start {
+                let mut chain = vec![(current, call)];
+                let mut process = current;
+                while process != start {
+                    let (caller, previous) = reached_by[&process];
+                    chain.push((caller, previous));
+                    process = caller;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            if call.callee != start && !reached_by.contains_key(&call.callee) {
+                reached_by.insert(call.callee, (current, call));
+                queue.push_back(call.callee);
+            }
+        }
+    }
+    None
+}
+
+/// Adds the process calls in `proc` that can happen before `proc` performs
+/// any action.
+fn add_unguarded_calls(
+    context: &AnalysisContext,
+    module: &IrModule,
+    proc: ProcId,
+    result: &mut Vec<UnguardedCall>,
+) {
+    match &module.get_proc(proc).value {
+        IrProcEnum::Action { value } => {
+            if let Some(callee) = get_called_process(context, module, NodeId::Action(*value)) {
+                result.push(UnguardedCall {
+                    callee,
+                    loc: module.get_action(*value).identifier_loc,
+                });
+            }
+        },
+        IrProcEnum::Concat { lhs, rhs } => {
+            add_unguarded_calls(context, module, *lhs, result);
+            if !is_guarding(context, module, *lhs, &mut Vec::new()) {
+                add_unguarded_calls(context, module, *rhs, result);
+            }
+        },
+        IrProcEnum::Add { lhs, rhs } |
+        IrProcEnum::Multi { lhs, rhs } |
+        IrProcEnum::Parallel { lhs, rhs } |
+        IrProcEnum::LeftParallel { lhs, rhs } => {
+            add_unguarded_calls(context, module, *lhs, result);
+            add_unguarded_calls(context, module, *rhs, result);
+        },
+        IrProcEnum::IfThen { then_proc, .. } => {
+            add_unguarded_calls(context, module, *then_proc, result);
+        },
+        IrProcEnum::IfThenElse { then_proc, else_proc, .. } => {
+            add_unguarded_calls(context, module, *then_proc, result);
+            add_unguarded_calls(context, module, *else_proc, result);
+        },
+        IrProcEnum::Allow { proc, .. } |
+        IrProcEnum::Block { proc, .. } |
+        IrProcEnum::Comm { proc } |
+        IrProcEnum::Hide { proc, .. } |
+        IrProcEnum::Rename { proc } |
+        IrProcEnum::Sum { proc, .. } => {
+            add_unguarded_calls(context, module, *proc, result);
+        },
+        IrProcEnum::Delta | IrProcEnum::Tau => {},
+    }
+}
+
+/// Returns whether `proc` always performs an action (or deadlocks) before it
+/// terminates, so that a process that follows it with `.` is guarded.
+///
+/// `visiting` holds the processes whose bodies are being checked, so that a
+/// recursive call is conservatively assumed not to guard.
+fn is_guarding(
+    context: &AnalysisContext,
+    module: &IrModule,
+    proc: ProcId,
+    visiting: &mut Vec<DefId>,
+) -> bool {
+    match &module.get_proc(proc).value {
+        IrProcEnum::Action { value } => {
+            let Some(callee) = get_called_process(context, module, NodeId::Action(*value)) else {
+                return true
+            };
+            if visiting.contains(&callee) {
+                return false;
+            }
+            let DefiningNode::Decl(decl) = get_defining_node_from_def(module, callee) else {
+                return false
+            };
+            let IrDeclEnum::Process { proc: body, .. } = &decl.value else {
+                return false
+            };
+            visiting.push(callee);
+            let result = is_guarding(context, module, *body, visiting);
+            visiting.pop();
+            result
+        },
+        IrProcEnum::Concat { lhs, rhs } => {
+            is_guarding(context, module, *lhs, visiting) ||
+                is_guarding(context, module, *rhs, visiting)
+        },
+        IrProcEnum::Multi { lhs, rhs } => {
+            is_guarding(context, module, *lhs, visiting) ||
+                is_guarding(context, module, *rhs, visiting)
+        },
+        IrProcEnum::Add { lhs, rhs } |
+        IrProcEnum::Parallel { lhs, rhs } |
+        IrProcEnum::LeftParallel { lhs, rhs } => {
+            is_guarding(context, module, *lhs, visiting) &&
+                is_guarding(context, module, *rhs, visiting)
+        },
+        IrProcEnum::IfThen { then_proc, .. } => {
+            is_guarding(context, module, *then_proc, visiting)
+        },
+        IrProcEnum::IfThenElse { then_proc, else_proc, .. } => {
+            is_guarding(context, module, *then_proc, visiting) &&
+                is_guarding(context, module, *else_proc, visiting)
+        },
+        IrProcEnum::Allow { proc, .. } |
+        IrProcEnum::Block { proc, .. } |
+        IrProcEnum::Comm { proc } |
+        IrProcEnum::Hide { proc, .. } |
+        IrProcEnum::Rename { proc } |
+        IrProcEnum::Sum { proc, .. } => {
+            is_guarding(context, module, *proc, visiting)
+        },
+        IrProcEnum::Delta | IrProcEnum::Tau => true,
+    }
+}
+
+/// Returns the process that is called by an action node, or `None` if the
+/// node refers to an action instead.
+fn get_called_process(context: &AnalysisContext, module: &IrModule, node: NodeId) -> Option<DefId> {
+    let def_id = query_def_of_name(context, node).ok()?;
+    match get_defining_node_from_def(module, def_id) {
+        DefiningNode::Decl(decl) if matches!(decl.value, IrDeclEnum::Process { .. }) => Some(def_id),
+        _ => None,
+    }
+}
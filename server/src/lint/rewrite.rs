@@ -0,0 +1,128 @@
+use crate::lint::{Lint, LintKind};
+use crate::source_mapping::{get_head_symbol, IdentifierIterator};
+use crate::util::{get_source_slice, range_contains};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId};
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
+
+/// Reports suspicious equations in `eqn` sections, namely:
+/// - variables in the right-hand side or condition that do not occur in the
+///   left-hand side
+/// - left-hand sides whose head symbol is a constructor instead of a map
+/// - equations that are exact duplicates of an earlier equation
+///
+/// Variables that are declared but not used by any equation are reported by
+/// the lint for unused declarations.
+pub fn get_rewrite_lints(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+) -> Vec<Lint> {
+    let mut result = Vec::new();
+    let mut seen_equations = HashMap::new();
+    for node in module {
+        let NodeId::RewriteRule(rule_id) = node else {
+            continue;
+        };
+        let rule = module.get_rewrite_rule(rule_id);
+
+        let lhs_vars = get_rewrite_vars_in(context, module, rule.lhs)
+            .into_iter()
+            .map(|(def_id, _)| def_id)
+            .collect::<HashSet<_>>();
+        let other_vars = get_rewrite_vars_in(context, module, rule.rhs)
+            .into_iter()
+            .chain(rule.condition.into_iter().flat_map(|condition| {
+                get_rewrite_vars_in(context, module, condition)
+            }));
+        for (def_id, loc) in other_vars {
+            if !lhs_vars.contains(&def_id) {
+                result.push(Lint {
+                    kind: LintKind::UnboundRewriteVariable,
+                    loc,
+                    message: format!(
+                        "variable `{}` does not occur in the left-hand side of this equation",
+                        get_source_slice(input, loc),
+                    ),
+                    related: Vec::new(),
+                });
+            }
+        }
+
+        if let Some(head) = get_head_symbol(module, rule.lhs) {
+            let head_def = query_def_of_name(context, head.into());
+            let head_node = head_def.map(|def| get_defining_node_from_def(module, def));
+            if let Ok(DefiningNode::Decl(decl)) = head_node {
+                if matches!(decl.value, IrDeclEnum::Constructor { .. }) {
+                    result.push(Lint {
+                        kind: LintKind::ConstructorHead,
+                        loc: module.get_node_loc(head.into()),
+                        message: format!(
+                            "the head symbol of this left-hand side is constructor `{}` \
+                                instead of a map",
+                            decl.identifier,
+                        ),
+                        related: vec![(decl.identifier_loc, "constructor declared here".to_owned())],
+                    });
+                }
+            }
+        }
+
+        let rule_loc = module.get_node_loc(node);
+        match seen_equations.entry(get_normalized_text(tokens, input, rule_loc)) {
+            Entry::Occupied(entry) => {
+                result.push(Lint {
+                    kind: LintKind::DuplicateEquation,
+                    loc: rule_loc,
+                    message: "this equation is a duplicate of an earlier equation".to_owned(),
+                    related: vec![(*entry.get(), "earlier equation".to_owned())],
+                });
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(rule_loc);
+            },
+        }
+    }
+    result
+}
+
+/// Returns the text of the tokens at `loc` without comments, separated by
+/// single spaces, so that equations that only differ in layout are equal.
+pub fn get_normalized_text(tokens: &[Token], input: &str, loc: SourceRange) -> String {
+    use LexicalElement::{Comment, DocComment};
+
+    tokens.iter()
+        .filter(|token| range_contains(loc, token.loc))
+        .filter(|token| !matches!(token.value, Comment(_) | DocComment(_)))
+        .map(|token| get_source_slice(input, token.loc))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the rewrite variables that are referred to inside an expression,
+/// along with the location of each reference.
+fn get_rewrite_vars_in(
+    context: &AnalysisContext,
+    module: &IrModule,
+    expr: ExprId,
+) -> Vec<(DefId, SourceRange)> {
+    IdentifierIterator::new(module, expr.into())
+        .filter(|(_, _, _, def_id)| def_id.is_none())
+        .filter_map(|(_, loc, node, _)| {
+            let def_id = query_def_of_name(context, node).ok()?;
+            match get_defining_node_from_def(module, def_id) {
+                DefiningNode::RewriteVar(_) => Some((def_id, loc)),
+                _ => None,
+            }
+        })
+        .collect()
+}
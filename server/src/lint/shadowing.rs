@@ -0,0 +1,77 @@
+use crate::lint::{Lint, LintKind};
+
+use nano_crl2::analysis::semantic::name_resolution::{
+    get_defs_in_context, NameLookup, NameLookupEnum,
+};
+use nano_crl2::ir::decl::IrDeclEnum;
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::iterator::{
+    DefiningNode, ParentIterator, get_def_data, get_defining_node_from_def,
+};
+use nano_crl2::ir::module::{IrModule, NodeId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+/// Reports binder variables (e.g. of `forall`) and `sum` variables that have
+/// the same name as a variable that is already visible at that point, such
+/// as a process parameter or a global variable.
+pub fn get_shadowing_lints(module: &IrModule) -> Vec<Lint> {
+    let mut result = Vec::new();
+    for node in module {
+        let (def_id, identifier, identifier_loc) = match node {
+            NodeId::Expr(expr_id) => match &module.get_expr(expr_id).value {
+                IrExprEnum::Binder { def_id, identifier, identifier_loc, .. } => {
+                    (*def_id, identifier, *identifier_loc)
+                },
+                _ => continue,
+            },
+            NodeId::Proc(proc_id) => match &module.get_proc(proc_id).value {
+                IrProcEnum::Sum { def_id, identifier, identifier_loc, .. } => {
+                    (*def_id, identifier, *identifier_loc)
+                },
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let Some(parent) = module.get_parent(node) else {
+            continue;
+        };
+
+        let shadowed = ParentIterator::new(module, parent)
+            .flat_map(|ancestor| get_defs_in_context(module, ancestor, &NameLookup {
+                value: NameLookupEnum::All,
+                identifier: None,
+                loc: module.get_node_loc(ancestor),
+            }))
+            .filter(|&other_def_id| other_def_id != def_id)
+            .find_map(|other_def_id| {
+                let (_, other_identifier, other_loc) = get_def_data(
+                    module,
+                    module.get_def_source(other_def_id),
+                )?;
+                if other_identifier != identifier {
+                    return None;
+                }
+                let description = match get_defining_node_from_def(module, other_def_id) {
+                    DefiningNode::Decl(decl) => match decl.value {
+                        IrDeclEnum::GlobalVariable { .. } => "global variable",
+                        _ => return None,
+                    },
+                    DefiningNode::BinderExpr { .. } => "bound variable",
+                    DefiningNode::Param(_) => "parameter",
+                    DefiningNode::SumProc { .. } => "sum variable",
+                    DefiningNode::RewriteVar(_) => "variable",
+                };
+                Some((description, other_loc))
+            });
+
+        if let Some((description, shadowed_loc)) = shadowed {
+            result.push(Lint {
+                kind: LintKind::Shadowing,
+                loc: identifier_loc,
+                message: format!("`{}` shadows a {} with the same name", identifier, description),
+                related: vec![(shadowed_loc, format!("`{}` is declared here", identifier))],
+            });
+        }
+    }
+    result
+}
@@ -0,0 +1,116 @@
+use crate::lint::{Lint, LintKind};
+use crate::source_mapping::{IdentifierIterator, get_head_symbol};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::iterator::ParentIterator;
+use nano_crl2::ir::module::{IrModule, NodeId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use std::collections::HashSet;
+
+/// Reports maps, actions, processes, rewrite variables, `sum` variables and
+/// binder variables that are declared but never referred to.
+///
+/// References from the equations of a map to that map itself, and calls from
+/// the body of a process to that process itself, do not count as uses.
+/// Definitions that share their name with a reference that does not resolve
+/// are not reported, since that reference may be meant for them.
+pub fn get_unused_lints(context: &AnalysisContext, module: &IrModule) -> Vec<Lint> {
+    let (used, unresolved) = get_used_defs(context, module);
+    let mut result = Vec::new();
+    for node in module {
+        let (description, def_id, identifier, identifier_loc) = match node {
+            NodeId::Decl(decl_id) => {
+                let decl = module.get_decl(decl_id);
+                let description = match decl.value {
+                    IrDeclEnum::Action { .. } => "action",
+                    IrDeclEnum::Map { .. } => "map",
+                    IrDeclEnum::Process { .. } => "process",
+                    _ => continue,
+                };
+                (description, decl.def_id, &decl.identifier, decl.identifier_loc)
+            },
+            NodeId::Expr(expr_id) => match &module.get_expr(expr_id).value {
+                IrExprEnum::Binder { def_id, identifier, identifier_loc, .. } => {
+                    ("variable", *def_id, identifier, *identifier_loc)
+                },
+                _ => continue,
+            },
+            NodeId::Proc(proc_id) => match &module.get_proc(proc_id).value {
+                IrProcEnum::Sum { def_id, identifier, identifier_loc, .. } => {
+                    ("sum variable", *def_id, identifier, *identifier_loc)
+                },
+                _ => continue,
+            },
+            NodeId::RewriteVar(rewrite_var_id) => {
+                let rewrite_var = module.get_rewrite_var(rewrite_var_id);
+                (
+                    "variable",
+                    rewrite_var.def_id,
+                    &rewrite_var.identifier,
+                    rewrite_var.identifier_loc,
+                )
+            },
+            _ => continue,
+        };
+
+        if used.contains(&def_id) || unresolved.contains(identifier.get_value()) {
+            continue;
+        }
+        result.push(Lint {
+            kind: LintKind::Unused,
+            loc: identifier_loc,
+            message: format!("{} `{}` is never used", description, identifier),
+            related: Vec::new(),
+        });
+    }
+    result
+}
+
+/// Resolves every name in the module once, and returns the definitions that
+/// are referred to from outside of their own equations or process body,
+/// together with the names that do not resolve.
+fn get_used_defs(
+    context: &AnalysisContext,
+    module: &IrModule,
+) -> (HashSet<DefId>, HashSet<String>) {
+    let mut used = HashSet::new();
+    let mut unresolved = HashSet::new();
+    let iterator = IdentifierIterator::new(module, NodeId::Module(module.id));
+    for (identifier, _, node, def_id) in iterator {
+        if def_id.is_some() {
+            continue;
+        }
+        let Ok(def_id) = query_def_of_name(context, node) else {
+            unresolved.insert(identifier.to_string());
+            continue;
+        };
+        if get_enclosing_def(context, module, node) != Some(def_id) {
+            used.insert(def_id);
+        }
+    }
+    (used, unresolved)
+}
+
+/// Returns the map whose equation contains `node`, or the process whose body
+/// contains `node`, or `None` if `node` is in neither.
+fn get_enclosing_def(context: &AnalysisContext, module: &IrModule, node: NodeId) -> Option<DefId> {
+    for ancestor in ParentIterator::new(module, node) {
+        match ancestor {
+            NodeId::RewriteRule(rule_id) => {
+                let head = get_head_symbol(module, module.get_rewrite_rule(rule_id).lhs)?;
+                return query_def_of_name(context, NodeId::Expr(head)).ok();
+            },
+            NodeId::Decl(decl_id) => {
+                let decl = module.get_decl(decl_id);
+                let is_process = matches!(decl.value, IrDeclEnum::Process { .. });
+                return is_process.then_some(decl.def_id);
+            },
+            _ => {},
+        }
+    }
+    None
+}
@@ -1,23 +1,54 @@
 
-use crate::def_info::{get_completion_item, get_def_info, DefInfoDisplay};
+use crate::builtin::{
+    get_builtin_completion_items, get_builtin_documentation, get_builtin_item,
+    get_builtin_item_of_lexical_element, BuiltinKind,
+};
+use crate::def_info::{get_completion_item, CompletionItemData};
+use crate::diagnostic_code::{
+    get_analysis_diagnostic_kind, get_lint_diagnostic_kind, get_other_declarations,
+    DUPLICATE_DEFINITION,
+};
+use crate::documentation::{get_def_documentation, get_expr_documentation};
+use crate::extract_map::get_extract_map_actions;
+use crate::extract_process::get_extract_process_actions;
+use crate::folding_range::get_folding_ranges;
+use crate::formatting::{format_model, format_on_type, format_range};
+use crate::inlay_hint::{get_inlay_hints, InlayHintConfig};
+use crate::inline::get_inline_actions;
+use crate::lint::get_lints;
+use crate::quick_fix::get_undeclared_name_fixes;
+use crate::section_order::get_sort_sections_actions;
+use crate::signature_change::get_signature_change_actions;
+use crate::signature_help::{get_call_at_position, get_signature_information};
 use crate::source_mapping::{
-    get_identifier_node_at_loc, get_def_context_at_loc, IdentifierIterator,
+    get_identifier_node_at_loc, get_def_context_at_loc, get_node_at_loc,
+    get_references, IdentifierIterator,
+};
+use crate::struct_generation::get_struct_generation_actions;
+use crate::suppression::Suppressions;
+use crate::util::{
+    get_end_position, get_source_slice, lsp_range_to_source_range, ranges_overlap,
+    source_range_to_lsp_range,
 };
-use crate::util::source_range_to_lsp_range;
 
 use nano_crl2::analysis::context::AnalysisContext;
 use nano_crl2::analysis::ir_conversion::module::query_ir_module;
 use nano_crl2::analysis::parsing::{query_ast_module, query_token_list};
 use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
-use nano_crl2::core::lexer::Token;
+use nano_crl2::core::lexer::{LexicalElement, Token};
 use nano_crl2::core::syntax::{ModuleId, SourceCursorPos, SourceRange};
 use nano_crl2::ir::decl::DefId;
 use nano_crl2::ir::module::{IrModule, NodeId};
 use nano_crl2::ir::iterator::get_def_data;
 use nano_crl2::model::module::Module;
-use tower_lsp::lsp_types::{CompletionItem, Position, Range};
+use tower_lsp::lsp_types::{
+    CodeActionOrCommand, CompletionItem, Diagnostic, DiagnosticRelatedInformation,
+    DiagnosticTag, Documentation, FoldingRange, InlayHint, Location, MarkupContent, MarkupKind,
+    Position, Range, SignatureHelp, TextEdit, Url,
+};
 
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 pub struct LspContext(pub Mutex<LspContextStore>);
@@ -27,6 +58,7 @@ impl LspContext {
         LspContext(Mutex::new(LspContextStore {
             analysis_context: AnalysisContext::new(),
             file_inputs: HashMap::new(),
+            input_strings: HashMap::new(),
             last_valid_irs: HashMap::new(),
         }))
     }
@@ -39,9 +71,13 @@ impl LspContext {
         value: String,
     ) -> Result<(), ()> {
         let mut guard = self.lock()?;
-        let new_module_id = guard.analysis_context.add_model_input(file_name.clone(), value);
+        let new_module_id = guard.analysis_context.add_model_input(
+            file_name.clone(),
+            value.clone(),
+        );
 
         guard.file_inputs.insert(file_name.clone(), new_module_id);
+        guard.input_strings.insert(new_module_id, value);
 
         Ok(())
     }
@@ -80,37 +116,379 @@ impl LspContext {
     ) -> Result<Vec<CompletionItem>, ()> {
         let mut guard = self.lock()?;
         let module = guard.get_last_valid_ir_module(file_name)?;
-        let result = get_def_context_at_loc(&module, loc)?
+        let mut result = get_def_context_at_loc(&module, loc)?
             .into_iter()
             .map(|def_id| {
-                get_completion_item(&guard.analysis_context, &module, def_id)
+                get_completion_item(&guard.analysis_context, &module, file_name, def_id)
             })
-            .collect();
+            .collect::<Vec<_>>();
+        result.extend(get_builtin_completion_items());
         Ok(result)
     }
 
-    /// Returns a formatted string of the definition that corresponds to the
-    /// identifier at the given location.
-    /// 
-    /// For instance, if `loc` is pointing at the `x` in `forall x : Nat, y`,
-    /// this will return `Ok(Some("x : Nat"))`.
-    pub fn query_definition_string(
+    /// Fills in the documentation of a completion item that was returned
+    /// earlier by `query_completion_items`.
+    pub fn resolve_completion_item(
+        &self,
+        mut item: CompletionItem,
+    ) -> Result<CompletionItem, ()> {
+        let Some(data) = item.data.clone() else {
+            return Ok(item)
+        };
+        let data = serde_json::from_value::<CompletionItemData>(data).map_err(|_| ())?;
+
+        let mut guard = self.lock()?;
+        let module = guard.get_last_valid_ir_module(&data.file_name)?;
+        let loc = SourceCursorPos::new(data.line, data.character);
+        let Some((_, _, Some(def_id))) = get_identifier_node_at_loc(&module, loc) else {
+            return Ok(item)
+        };
+        let documentation = guard.get_def_documentation(&module, &data.file_name, def_id)?;
+        drop(guard);
+
+        item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation,
+        }));
+        Ok(item)
+    }
+
+    /// Returns the source range of the hovered node at the given location,
+    /// along with a Markdown description of it.
+    ///
+    /// If `loc` is pointing at an identifier, this describes the definition
+    /// that it refers to (see `get_def_documentation`). For instance, if `loc`
+    /// is pointing at the `x` in `forall x : Nat, y`, the description starts
+    /// with a code block containing `x: Nat`. If `loc` is pointing at a
+    /// built-in sort, operator or function, this shows its description from
+    /// the standard library. Otherwise, if `loc` is pointing at a data
+    /// expression such as `x + 1`, this shows the sort of the smallest
+    /// expression containing `loc`.
+    pub fn query_hover(
         &self,
         file_name: &str,
         loc: SourceCursorPos,
-    ) -> Result<Option<String>, ()> {
+    ) -> Result<Option<(SourceRange, String)>, ()> {
         let mut guard = self.lock()?;
         let module = guard.get_last_valid_ir_module(file_name)?;
-        let Some((_, node, def_id)) = get_identifier_node_at_loc(&module, loc) else {
+        if let Some((identifier_loc, node, def_id)) = get_identifier_node_at_loc(&module, loc) {
+            let defining_id = match def_id {
+                Some(def_id) => Ok(def_id),
+                None => query_def_of_name(&guard.analysis_context, node),
+            };
+            if let Ok(defining_id) = defining_id {
+                let documentation = guard.get_def_documentation(&module, file_name, defining_id)?;
+                return Ok(Some((identifier_loc, documentation)));
+            }
+        }
+
+        let tokens = query_token_list(&guard.analysis_context, module.id)?;
+        let input = guard.get_input_string(module.id)?;
+        let (node_loc, node) = get_node_at_loc(&module, loc);
+        let expr_documentation = match node {
+            NodeId::Expr(expr_id) => {
+                get_expr_documentation(&guard.analysis_context, &module, input, expr_id)
+            },
+            _ => None,
+        };
+        let builtin = tokens.iter()
+            .find(|token| token.loc.contains_cursor(loc))
+            .and_then(|token| {
+                let item = match &token.value {
+                    LexicalElement::Identifier(_) => {
+                        get_builtin_item(get_source_slice(input, token.loc))
+                    },
+                    value => get_builtin_item_of_lexical_element(value),
+                };
+                item.map(|item| (token.loc, item))
+            });
+
+        match (builtin, expr_documentation) {
+            (Some((_, item)), Some(expr_documentation)) if item.kind != BuiltinKind::Sort => {
+                let documentation = format!(
+                    "{}\n\n---\n\n{}",
+                    get_builtin_documentation(item),
+                    expr_documentation,
+                );
+                Ok(Some((node_loc, documentation)))
+            },
+            (Some((token_loc, item)), _) => {
+                Ok(Some((token_loc, get_builtin_documentation(item))))
+            },
+            (None, Some(expr_documentation)) => Ok(Some((node_loc, expr_documentation))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Returns the signatures of all definitions that can be called by the
+    /// call that `position` is inside of, along with the active argument.
+    ///
+    /// All overloads with the name of the called identifier are included.
+    pub fn query_signature_help(
+        &self,
+        file_name: &str,
+        position: Position,
+    ) -> Result<Option<SignatureHelp>, ()> {
+        let mut guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        // the call is looked up in the newest tokens, since the user is most
+        // likely still typing it
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let Some((callee_index, active_parameter)) = get_call_at_position(&tokens, position) else {
             return Ok(None)
         };
-        let defining_id = match def_id {
-            Some(def_id) => def_id,
-            None => query_def_of_name(&guard.analysis_context, node)?,
+        let callee_name = get_source_slice(
+            guard.get_input_string(module_id)?,
+            tokens[callee_index].loc,
+        ).to_owned();
+
+        let module = guard.get_last_valid_ir_module(file_name)?;
+        let module_tokens = query_token_list(&guard.analysis_context, module.id)?;
+        let input = guard.get_input_string(module.id)?;
+        let loc = SourceCursorPos::new(position.line, position.character);
+        let mut visited = HashSet::new();
+        let mut signatures = Vec::new();
+        for def_id in get_def_context_at_loc(&module, loc)? {
+            if !visited.insert(def_id) {
+                continue;
+            }
+            let identifier = get_def_data(&module, module.get_def_source(def_id)).unwrap().1;
+            if identifier.to_string() != callee_name {
+                continue;
+            }
+            signatures.extend(get_signature_information(
+                &guard.analysis_context,
+                &module,
+                &module_tokens,
+                input,
+                def_id,
+            ));
+        }
+        if signatures.is_empty() {
+            return Ok(None);
+        }
+
+        let active_signature = signatures.iter()
+            .position(|signature| {
+                let parameter_count = signature.parameters.as_ref().map_or(0, |p| p.len());
+                parameter_count > active_parameter as usize
+            })
+            .unwrap_or(0);
+        Ok(Some(SignatureHelp {
+            signatures,
+            active_signature: Some(active_signature as u32),
+            active_parameter: Some(active_parameter),
+        }))
+    }
+
+    /// Returns the inlay hints that lie inside the given range.
+    ///
+    /// Unlike most queries, this does not fall back to the last valid IR,
+    /// since the hints would then be placed at outdated positions.
+    pub fn query_inlay_hints(
+        &self,
+        file_name: &str,
+        range: Range,
+        config: &InlayHintConfig,
+    ) -> Result<Vec<InlayHint>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
         };
-        let def_info = get_def_info(&guard.analysis_context, &module, defining_id);
+        let module = query_ir_module(&guard.analysis_context, module_id)?;
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let hints = get_inlay_hints(&guard.analysis_context, &module, &tokens, config);
         drop(guard);
-        Ok(Some(DefInfoDisplay::new(&module, &def_info).to_string()))
+
+        let result = hints.into_iter()
+            .filter(|hint| range.start <= hint.position && hint.position <= range.end)
+            .collect();
+        Ok(result)
+    }
+
+    /// Returns the quick fixes for the names in the given range of the newest
+    /// version of the file that cannot be resolved.
+    ///
+    /// The unresolved names are looked up here, since the server does not
+    /// publish the name resolution errors of nanoCRL2 itself. The fixes are
+    /// linked to the given diagnostics of the client that overlap the name.
+    pub fn query_quick_fixes(
+        &self,
+        file_name: &str,
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Result<Vec<CodeActionOrCommand>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        let module = query_ir_module(&guard.analysis_context, module_id)?;
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let uri = Url::parse(file_name).map_err(|_| ())?;
+        let loc = lsp_range_to_source_range(range);
+
+        let mut result = Vec::new();
+        let iterator = IdentifierIterator::new(&module, NodeId::Module(module.id));
+        for (_, name_loc, node, def_id) in iterator {
+            if def_id.is_some() || !ranges_overlap(loc, name_loc) {
+                continue;
+            }
+            if query_def_of_name(&guard.analysis_context, node).is_ok() {
+                continue;
+            }
+            let name_diagnostics = diagnostics.iter()
+                .filter(|diagnostic| {
+                    ranges_overlap(lsp_range_to_source_range(diagnostic.range), name_loc)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            result.extend(get_undeclared_name_fixes(
+                &guard.analysis_context,
+                &module,
+                &tokens,
+                &uri,
+                name_loc,
+                &name_diagnostics,
+            ).into_iter().map(CodeActionOrCommand::CodeAction));
+        }
+        Ok(result)
+    }
+
+    /// Returns the refactorings that can be applied to the given range of the
+    /// newest version of the file.
+    pub fn query_refactorings(
+        &self,
+        file_name: &str,
+        range: Range,
+    ) -> Result<Vec<CodeActionOrCommand>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        let module = query_ir_module(&guard.analysis_context, module_id)?;
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let input = guard.get_input_string(module_id)?;
+        let uri = Url::parse(file_name).map_err(|_| ())?;
+        let loc = lsp_range_to_source_range(range);
+
+        let mut result = Vec::new();
+        result.extend(get_extract_map_actions(
+            &guard.analysis_context,
+            &module,
+            &tokens,
+            input,
+            &uri,
+            loc,
+        ));
+        result.extend(get_extract_process_actions(
+            &guard.analysis_context,
+            &module,
+            &tokens,
+            input,
+            &uri,
+            loc,
+        ));
+        result.extend(get_inline_actions(
+            &guard.analysis_context,
+            &module,
+            &tokens,
+            input,
+            &uri,
+            loc,
+        ));
+        result.extend(get_signature_change_actions(
+            &guard.analysis_context,
+            &module,
+            &tokens,
+            input,
+            &uri,
+            loc,
+        ));
+        result.extend(get_struct_generation_actions(
+            &guard.analysis_context,
+            &module,
+            &tokens,
+            &uri,
+            loc,
+        ));
+        result.extend(get_sort_sections_actions(&tokens, input, &uri));
+        Ok(result.into_iter().map(CodeActionOrCommand::CodeAction).collect())
+    }
+
+    /// Returns the folding ranges of the newest version of the file.
+    pub fn query_folding_ranges(&self, file_name: &str) -> Result<Vec<FoldingRange>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        Ok(get_folding_ranges(&tokens))
+    }
+
+    /// Returns the edits that format the newest version of the file, where
+    /// `indent` is the text of one level of indentation.
+    ///
+    /// Only files that can be parsed are formatted.
+    pub fn query_formatting(&self, file_name: &str, indent: &str) -> Result<Vec<TextEdit>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        query_ast_module(&guard.analysis_context, module_id)?;
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let input = guard.get_input_string(module_id)?;
+
+        let Some(text) = format_model(&tokens, input, indent) else {
+            return Err(())
+        };
+        if text == input {
+            return Ok(Vec::new());
+        }
+        let range = Range::new(Position::new(0, 0), get_end_position(input));
+        Ok(vec![TextEdit::new(range, text)])
+    }
+
+    /// Returns the edits that format the declarations in the given range of
+    /// the newest version of the file, see `query_formatting`.
+    pub fn query_range_formatting(
+        &self,
+        file_name: &str,
+        range: Range,
+        indent: &str,
+    ) -> Result<Vec<TextEdit>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        query_ast_module(&guard.analysis_context, module_id)?;
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let input = guard.get_input_string(module_id)?;
+
+        format_range(&tokens, input, indent, range).ok_or(())
+    }
+
+    /// Returns the edits that re-indent the declaration at `position` of the
+    /// newest version of the file, after `typed` was typed there.
+    ///
+    /// Unlike the other kinds of formatting, this also works for files that
+    /// cannot be parsed, since these are common while typing.
+    pub fn query_on_type_formatting(
+        &self,
+        file_name: &str,
+        position: Position,
+        typed: &str,
+        indent: &str,
+    ) -> Result<Vec<TextEdit>, ()> {
+        let guard = self.lock()?;
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Err(())
+        };
+        let tokens = query_token_list(&guard.analysis_context, module_id)?;
+        let input = guard.get_input_string(module_id)?;
+
+        format_on_type(&tokens, input, indent, position, typed).ok_or(())
     }
 
     pub fn query_definition(
@@ -140,30 +518,7 @@ impl LspContext {
             source_id.get_module_id(),
         )?;
 
-        let source_identifier = get_def_data(&ir_module, source_id).unwrap().1;
-
-        let start_node = if matches!(source_id, NodeId::Decl(..) | NodeId::Param(..) | NodeId::RewriteVar(..)) {
-            let Some(parent) = ir_module.get_parent(source_id) else {
-                return Ok(Vec::new())
-            };
-            parent
-        } else {
-            source_id
-        };
-
-        let mut result = Vec::new();
-        let iterator = IdentifierIterator::new(&ir_module, start_node);
-        for (identifier, loc, target, def_id) in iterator {
-            if def_id.is_some() || identifier != source_identifier {
-                continue; // easy optimization
-            }
-            let def = query_def_of_name(&guard.analysis_context, target)?;
-            let def_source = ir_module.get_def_source(def);
-            if def_source == source_id {
-                result.push(loc);
-            }
-        }
-        Ok(result)
+        get_references(&guard.analysis_context, &ir_module, source_id)
     }
 
     pub fn get_diagnostics(
@@ -188,6 +543,18 @@ impl LspContext {
             return Vec::new()
         };
 
+        let tokens = query_token_list(&guard.analysis_context, module).ok();
+        let input = guard.get_input_string(module).ok();
+        let uri = Url::parse(file_name).ok();
+        let suppressions = match (&tokens, input) {
+            (Some(tokens), Some(input)) => Some(Suppressions::from_tokens(tokens, input)),
+            _ => None,
+        };
+
+        // convert the module to IR, so that the errors of the analysis are
+        // reported as well and not just those of parsing
+        let _ = query_ir_module(&guard.analysis_context, module);
+
         let mut result = Vec::new();
         guard.analysis_context.for_each_diagnostic(|diagnostic| {
             if diagnostic.module != Some(module) {
@@ -206,19 +573,124 @@ impl LspContext {
             } else {
                 Range::new(Position::new(0, 0), Position::new(0, 0))
             };
-            result.push(tower_lsp::lsp_types::Diagnostic::new(
+
+            let other_declarations = match (diagnostic.loc, &tokens, input) {
+                (Some(loc), Some(tokens), Some(input)) => {
+                    get_other_declarations(tokens, input, loc)
+                },
+                _ => Vec::new(),
+            };
+            let kind = get_analysis_diagnostic_kind(diagnostic.component, &other_declarations);
+            if let (Some(suppressions), Some(loc)) = (&suppressions, diagnostic.loc) {
+                let is_error = matches!(
+                    diagnostic.severity,
+                    nano_crl2::core::diagnostic::DiagnosticSeverity::Error,
+                );
+                if !is_error && suppressions.is_suppressed(kind, loc) {
+                    return;
+                }
+            }
+
+            // point to the other declarations of a duplicate definition
+            let mut related_information = Vec::new();
+            if *kind == DUPLICATE_DEFINITION {
+                if let Some(uri) = &uri {
+                    for other_loc in other_declarations {
+                        let other_range = source_range_to_lsp_range(other_loc);
+                        related_information.push(DiagnosticRelatedInformation {
+                            location: Location::new(uri.clone(), other_range),
+                            message: "other definition with the same name".to_owned(),
+                        });
+                    }
+                }
+            }
+
+            result.push(tower_lsp::lsp_types::Diagnostic {
                 range,
-                Some(severity),
-                None, // error code
-                Some("nanoCRL2-lsp".to_owned()),
-                diagnostic.message.clone(),
-                None, // related information
-                None, // tag
-            ));
+                severity: Some(severity),
+                code: Some(kind.get_lsp_code()),
+                code_description: kind.get_code_description(),
+                source: Some("nanoCRL2-lsp".to_owned()),
+                message: diagnostic.message.clone(),
+                related_information: if related_information.is_empty() {
+                    None
+                } else {
+                    Some(related_information)
+                },
+                tags: None,
+                data: None,
+            });
         });
         result
     }
 
+    /// Returns the warnings of all lints for the given file.
+    ///
+    /// This only uses the newest IR of the file, since outdated locations
+    /// would be confusing, so this is empty if the file has errors.
+    pub fn get_lint_diagnostics(
+        &self,
+        file_name: &str,
+    ) -> Vec<tower_lsp::lsp_types::Diagnostic> {
+        let Ok(guard) = self.lock() else {
+            return Vec::new()
+        };
+        let Some(&module_id) = guard.file_inputs.get(file_name) else {
+            return Vec::new()
+        };
+        let Ok(module) = query_ir_module(&guard.analysis_context, module_id) else {
+            return Vec::new()
+        };
+        let (Ok(tokens), Ok(input)) = (
+            query_token_list(&guard.analysis_context, module_id),
+            guard.get_input_string(module_id),
+        ) else {
+            return Vec::new()
+        };
+        let lints = get_lints(&guard.analysis_context, &module, &tokens, input);
+        let suppressions = Suppressions::from_tokens(&tokens, input);
+        drop(guard);
+
+        let Ok(uri) = Url::parse(file_name) else {
+            return Vec::new()
+        };
+        lints.into_iter()
+            .filter(|lint| {
+                lint.kind.get_severity() == tower_lsp::lsp_types::DiagnosticSeverity::ERROR ||
+                    !suppressions.is_suppressed(get_lint_diagnostic_kind(lint.kind), lint.loc)
+            })
+            .map(|lint| {
+                let related_information = lint.related.into_iter()
+                    .map(|(loc, message)| DiagnosticRelatedInformation {
+                        location: Location::new(uri.clone(), source_range_to_lsp_range(loc)),
+                        message,
+                    })
+                    .collect::<Vec<_>>();
+                let tags = if lint.kind.is_unnecessary() {
+                    Some(vec![DiagnosticTag::UNNECESSARY])
+                } else {
+                    None
+                };
+                let kind = get_lint_diagnostic_kind(lint.kind);
+                tower_lsp::lsp_types::Diagnostic {
+                    range: source_range_to_lsp_range(lint.loc),
+                    severity: Some(lint.kind.get_severity()),
+                    code: Some(kind.get_lsp_code()),
+                    code_description: kind.get_code_description(),
+                    source: Some("nanoCRL2-lsp".to_owned()),
+                    message: lint.message,
+                    related_information: if related_information.is_empty() {
+                        None
+                    } else {
+                        Some(related_information)
+                    },
+                    tags,
+                    data: None,
+                }
+            })
+            .collect()
+    }
+
     fn lock(&self) -> Result<MutexGuard<LspContextStore>, ()> {
         self.0.lock().map_err(|_error| {
             // TODO report error somewhere
@@ -230,6 +702,9 @@ impl LspContext {
 pub struct LspContextStore {
     analysis_context: AnalysisContext,
     file_inputs: HashMap<String, ModuleId>,
+    /// The input string of each model input that is still stored in the
+    /// analysis context, which is needed to show parts of the source.
+    input_strings: HashMap<ModuleId, String>,
     /// Since a user is constantly typing and modifying the file string, it is
     /// often useful to have some kind of reference to the last valid IR, so we
     /// can still extract semantic information.
@@ -249,6 +724,7 @@ impl LspContextStore {
                     Entry::Occupied(mut entry) => {
                         if *entry.get() != module_id {
                             self.analysis_context.remove_model_input(*entry.get());
+                            self.input_strings.remove(entry.get());
                             *entry.get_mut() = module_id;
                         }
                     },
@@ -272,4 +748,32 @@ impl LspContextStore {
             },
         }
     }
+
+    /// Returns the Markdown documentation of a definition in the given
+    /// module, see `get_def_documentation`.
+    pub fn get_def_documentation(
+        &self,
+        module: &IrModule,
+        file_name: &str,
+        def_id: DefId,
+    ) -> Result<String, ()> {
+        let tokens = query_token_list(&self.analysis_context, module.id)?;
+        let input = self.get_input_string(module.id)?;
+        Ok(get_def_documentation(
+            &self.analysis_context,
+            module,
+            file_name,
+            &tokens,
+            input,
+            def_id,
+        ))
+    }
+
+    /// Returns the input string from which the given module was created.
+    pub fn get_input_string(&self, module_id: ModuleId) -> Result<&str, ()> {
+        match self.input_strings.get(&module_id) {
+            Some(input) => Ok(input),
+            None => Err(()),
+        }
+    }
 }
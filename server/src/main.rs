@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use nano_crl2::core::syntax::SourceCursorPos;
 
 use nano_crl2_lsp::core::{Editor, EditorConfig};
+use nano_crl2_lsp::inlay_hint::InlayHintConfig;
 use nano_crl2_lsp::lsp_context::LspContext;
 use nano_crl2_lsp::semantic_token::{
     get_semantic_tokens_from_tokens,
@@ -29,6 +30,7 @@ async fn main() {
             editor: Editor::VsCode,
             check_parse_errors_continuously: true,
             check_errors_continuously: false,
+            check_lints_continuously: true,
         },
     })
     .finish();
@@ -49,12 +51,12 @@ impl LanguageServer for Backend {
             server_info: None,
             offset_encoding: None,
             capabilities: ServerCapabilities {
-                inlay_hint_provider: None,
+                inlay_hint_provider: Some(OneOf::Left(true)),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
                 completion_provider: Some(CompletionOptions {
-                    resolve_provider: Some(false),
+                    resolve_provider: Some(true),
                     trigger_characters: Some(vec![".".to_string()]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
@@ -100,6 +102,27 @@ impl LanguageServer for Backend {
                 references_provider: Some(OneOf::Left(true)),
                 rename_provider: Some(OneOf::Left(true)),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: ";".to_string(),
+                    more_trigger_character: Some(vec!["\n".to_string()]),
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    code_action_kinds: Some(vec![
+                        CodeActionKind::QUICKFIX,
+                        CodeActionKind::REFACTOR,
+                        CodeActionKind::SOURCE,
+                    ]),
+                    work_done_progress_options: Default::default(),
+                    resolve_provider: None,
+                })),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
                 ..ServerCapabilities::default()
             },
         })
@@ -242,6 +265,16 @@ impl LanguageServer for Backend {
         Ok(Some(CompletionResponse::Array(result)))
     }
 
+    async fn completion_resolve(&self, params: CompletionItem) -> Result<CompletionItem> {
+        let message = format!("completion resolve {:?}", params.label);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        match self.lsp_context.resolve_completion_item(params.clone()) {
+            Ok(item) => Ok(item),
+            Err(()) => Ok(params),
+        }
+    }
+
     async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
         let document_uri = params.text_document_position.text_document.uri;
         let position = params.text_document_position.position;
@@ -282,7 +315,7 @@ impl LanguageServer for Backend {
 
         self.client.log_message(MessageType::LOG, "hover").await;
 
-        let detail = match self.lsp_context.query_definition_string(
+        let (range, documentation) = match self.lsp_context.query_hover(
             document_uri.as_str(),
             SourceCursorPos::new(position.line, position.character),
         ) {
@@ -292,18 +325,144 @@ impl LanguageServer for Backend {
         };
 
         Ok(Some(Hover {
-            contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
-                language: "mcrl2".to_owned(),
-                value: detail,
-            })),
-            range: None,
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: documentation,
+            }),
+            range: Some(source_range_to_lsp_range(range)),
         }))
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let document_uri = params.text_document.uri;
+
+        let message = format!("code action {:?} {:?}", document_uri.path(), params.range);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        let mut actions = self.lsp_context.query_quick_fixes(
+            document_uri.as_str(),
+            params.range,
+            &params.context.diagnostics,
+        ).unwrap_or_default();
+        actions.extend(self.lsp_context.query_refactorings(
+            document_uri.as_str(),
+            params.range,
+        ).unwrap_or_default());
+        Ok(Some(actions))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let document_uri = params.text_document.uri;
+
+        let message = format!("folding range {:?}", document_uri.path());
+        self.client.log_message(MessageType::LOG, message).await;
+
+        match self.lsp_context.query_folding_ranges(document_uri.as_str()) {
+            Ok(ranges) => Ok(Some(ranges)),
+            Err(()) => Ok(None),
+        }
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let document_uri = params.text_document.uri;
+
+        let message = format!("formatting {:?}", document_uri.path());
+        self.client.log_message(MessageType::LOG, message).await;
+
+        let indent = get_indent(&params.options);
+        match self.lsp_context.query_formatting(document_uri.as_str(), &indent) {
+            Ok(edits) => Ok(Some(edits)),
+            Err(()) => Ok(None),
+        }
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let document_uri = params.text_document.uri;
+
+        let message = format!("range formatting {:?} {:?}", document_uri.path(), params.range);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        let indent = get_indent(&params.options);
+        let edits = self.lsp_context.query_range_formatting(
+            document_uri.as_str(),
+            params.range,
+            &indent,
+        );
+        match edits {
+            Ok(edits) => Ok(Some(edits)),
+            Err(()) => Ok(None),
+        }
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let document_uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let message = format!("on type formatting {:?} {:?}", document_uri.path(), position);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        let indent = get_indent(&params.options);
+        let edits = self.lsp_context.query_on_type_formatting(
+            document_uri.as_str(),
+            position,
+            &params.ch,
+            &indent,
+        );
+        match edits {
+            Ok(edits) => Ok(Some(edits)),
+            Err(()) => Ok(None),
+        }
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let document_uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let message = format!("signature help {:?} {:?}", document_uri.path(), position);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        match self.lsp_context.query_signature_help(document_uri.as_str(), position) {
+            Ok(value) => Ok(value),
+            Err(()) => Ok(None),
+        }
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let document_uri = params.text_document.uri;
+
+        let message = format!("inlay hint {:?} {:?}", document_uri.path(), params.range);
+        self.client.log_message(MessageType::LOG, message).await;
+
+        let settings = self.client.configuration(vec![ConfigurationItem {
+            scope_uri: Some(document_uri.clone()),
+            section: Some("nano-crl2-lsp.inlayHints".to_owned()),
+        }]).await;
+        let config = match settings.as_ref().map(|values| values.first()) {
+            Ok(Some(value)) => InlayHintConfig::from_settings(value),
+            _ => InlayHintConfig::default(),
+        };
+
+        match self.lsp_context.query_inlay_hints(document_uri.as_str(), params.range, &config) {
+            Ok(hints) => Ok(Some(hints)),
+            Err(()) => Ok(None),
+        }
+    }
+
     async fn did_change_configuration(&self, _: DidChangeConfigurationParams) {
         self.client
             .log_message(MessageType::INFO, "configuration changed!")
             .await;
+
+        // the inlay hint settings are requested again on every refresh
+        if let Err(error) = self.client.inlay_hint_refresh().await {
+            self.client.log_message(MessageType::ERROR, error).await;
+        }
     }
 
     async fn did_change_workspace_folders(&self, _: DidChangeWorkspaceFoldersParams) {
@@ -359,7 +518,16 @@ impl Backend {
             if self.lsp_context.query_ast(params.uri.as_str()).is_err() {
                 self.lsp_context.get_diagnostics(params.uri.as_str())
             } else {
-                Vec::new()
+                // the lints run first, since they run the analysis queries
+                // whose errors are then reported along with the lints
+                let lint_diagnostics = if self.editor_config.check_lints_continuously {
+                    self.lsp_context.get_lint_diagnostics(params.uri.as_str())
+                } else {
+                    Vec::new()
+                };
+                let mut diagnostics = self.lsp_context.get_diagnostics(params.uri.as_str());
+                diagnostics.extend(lint_diagnostics);
+                diagnostics
             }
         } else {
             Vec::new()
@@ -457,3 +625,13 @@ enum CursorSourceClick {
     Definition(LocationLink),
     References(Vec<Location>, Range),
 }
+
+/// Returns the text of one level of indentation in the given formatting
+/// options.
+fn get_indent(options: &FormattingOptions) -> String {
+    if options.insert_spaces {
+        " ".repeat(options.tab_size as usize)
+    } else {
+        "\t".to_owned()
+    }
+}
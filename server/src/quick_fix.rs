@@ -0,0 +1,275 @@
+use crate::signature_help::{get_declared_sort, split_function_sort};
+use crate::source_mapping::{get_def_context_at_loc, get_identifier_node_at_loc};
+use crate::util::{
+    get_declaration_insert_line, get_edit_distance, get_token_index, is_section_keyword,
+    source_range_to_lsp_range,
+};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::query_def_of_name;
+use nano_crl2::analysis::semantic::sort_resolution::query_resolved_sort;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::{SourceCursorPos, SourceRange};
+use nano_crl2::ir::decl::IrDeclEnum;
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::expr::{BinderExprOp, IrExprEnum};
+use nano_crl2::ir::iterator::{
+    DefiningNode, ParentIterator, get_def_data, get_defining_node_from_def,
+};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId};
+use nano_crl2::ir::proc::IrProcEnum;
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Diagnostic, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// The maximal number of "did you mean" suggestions for a single name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the quick fixes for a name that could not be resolved, namely:
+/// - declaring it as an action if it is used as an action
+/// - declaring it as a map if it is used in a data expression, with the sorts
+///   of the arguments and the sort that is expected at the call site
+/// - declaring it as a variable if it is used in an equation
+/// - replacing it by a similar name that is visible at that point
+///
+/// The declarations are only offered if all of their sorts can be determined.
+/// The fixes refer to `diagnostics`, which are the diagnostics of the client
+/// about this name, if any.
+pub fn get_undeclared_name_fixes(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    uri: &Url,
+    name_loc: SourceRange,
+    diagnostics: &[Diagnostic],
+) -> Vec<CodeAction> {
+    let Some(name_index) = get_token_index(tokens, name_loc) else {
+        return Vec::new()
+    };
+    let LexicalElement::Identifier(name) = &tokens[name_index].value else {
+        return Vec::new()
+    };
+    let cursor = SourceCursorPos::new(name_loc.get_start_line(), name_loc.get_start_char());
+
+    let mut result = Vec::new();
+    let create_fix = |title: String, edit: TextEdit, is_preferred: bool| CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: (!diagnostics.is_empty()).then(|| diagnostics.to_vec()),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), vec![edit])]))),
+        is_preferred: Some(is_preferred),
+        ..CodeAction::default()
+    };
+
+    match get_identifier_node_at_loc(module, cursor) {
+        Some((_, NodeId::Action(action_id), _)) => {
+            let args = &module.get_action(action_id).args;
+            if let Some(sorts) = get_sort_strings(context, module, args) {
+                let declaration = if sorts.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}: {}", name, sorts.join(" # "))
+                };
+                let edit = get_declaration_edit(tokens, name_index, "act", &declaration);
+                result.push(create_fix(format!("Add `act {}`", declaration), edit, false));
+            }
+        },
+        Some((_, NodeId::Expr(expr_id), _)) => {
+            // if the name is called, the map is declared with a function sort
+            let (call, args) = match module.get_parent(NodeId::Expr(expr_id)) {
+                Some(NodeId::Expr(parent_id)) => match &module.get_expr(parent_id).value {
+                    IrExprEnum::Apply { callee, args } if *callee == expr_id => {
+                        (parent_id, args.as_slice())
+                    },
+                    _ => (expr_id, [].as_slice()),
+                },
+                _ => (expr_id, [].as_slice()),
+            };
+            let result_sort = get_expected_sort(context, module, call);
+            let in_equation = ParentIterator::new(module, NodeId::Expr(expr_id))
+                .any(|ancestor| matches!(ancestor, NodeId::RewriteRule(_)));
+
+            if let Some(sort) = result_sort.as_ref().filter(|_| in_equation && args.is_empty()) {
+                let declaration = format!("{}: {}", name, sort);
+                if let Some(edit) = get_var_declaration_edit(tokens, name_index, &declaration) {
+                    result.push(create_fix(format!("Add `var {}`", declaration), edit, false));
+                }
+            }
+            let arg_sorts = get_sort_strings(context, module, args);
+            if let (Some(arg_sorts), Some(sort)) = (arg_sorts, result_sort) {
+                let declaration = if arg_sorts.is_empty() {
+                    format!("{}: {}", name, sort)
+                } else {
+                    format!("{}: {} -> {}", name, arg_sorts.join(" # "), sort)
+                };
+                let edit = get_declaration_edit(tokens, name_index, "map", &declaration);
+                result.push(create_fix(format!("Add `map {}`", declaration), edit, false));
+            }
+        },
+        _ => {},
+    }
+
+    let suggestions = get_similar_names(module, cursor, name);
+    let only_suggestion = suggestions.len() == 1;
+    for suggestion in suggestions {
+        let edit = TextEdit::new(source_range_to_lsp_range(name_loc), suggestion.clone());
+        result.push(create_fix(format!("Did you mean `{}`?", suggestion), edit, only_suggestion));
+    }
+    result
+}
+
+/// Returns the names that are visible at `cursor` and that are at most a few
+/// edits away from `name`, the most similar first.
+fn get_similar_names(module: &IrModule, cursor: SourceCursorPos, name: &str) -> Vec<String> {
+    let Ok(def_ids) = get_def_context_at_loc(module, cursor) else {
+        return Vec::new()
+    };
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut candidates = def_ids.into_iter()
+        .filter_map(|def_id| {
+            let (_, identifier, _) = get_def_data(module, module.get_def_source(def_id))?;
+            let identifier = identifier.to_string();
+            let distance = get_edit_distance(name, &identifier);
+            (distance > 0 && distance <= max_distance).then_some((distance, identifier))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup_by(|lhs, rhs| lhs.1 == rhs.1);
+    candidates.into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, identifier)| identifier)
+        .collect()
+}
+
+/// Returns the sort that is expected for an expression, based on where it is
+/// used, e.g. the parameter sort of the action that it is an argument of.
+fn get_expected_sort(
+    context: &AnalysisContext,
+    module: &IrModule,
+    expr: ExprId,
+) -> Option<String> {
+    match module.get_parent(NodeId::Expr(expr))? {
+        parent @ NodeId::Action(action_id) => {
+            let index = module.get_action(action_id).args.iter().position(|&arg| arg == expr)?;
+            let def_id = query_def_of_name(context, parent).ok()?;
+            let DefiningNode::Decl(decl) = get_defining_node_from_def(module, def_id) else {
+                return None
+            };
+            match &decl.value {
+                IrDeclEnum::Action { params } => {
+                    get_sort_string(context, module, *params.get(index)?)
+                },
+                IrDeclEnum::Process { params, .. } => {
+                    let param = module.get_param(*params.get(index)?);
+                    get_sort_string(context, module, param.sort)
+                },
+                _ => None,
+            }
+        },
+        NodeId::Proc(proc_id) => match module.get_proc(proc_id).value {
+            IrProcEnum::IfThen { condition, .. } |
+            IrProcEnum::IfThenElse { condition, .. } if condition == expr => {
+                Some("Bool".to_owned())
+            },
+            _ => None,
+        },
+        NodeId::Expr(parent_id) => match &module.get_expr(parent_id).value {
+            IrExprEnum::Apply { callee, args } => {
+                let index = args.iter().position(|&arg| arg == expr)?;
+                let callee_def = query_def_of_name(context, (*callee).into()).ok()?;
+                let callee_sort = get_declared_sort(module, callee_def)?;
+                let (params, _) = split_function_sort(context, module, callee_sort)?;
+                params.into_iter().nth(index)
+            },
+            IrExprEnum::Binder { op: BinderExprOp::Exists | BinderExprOp::Forall, .. } => {
+                Some("Bool".to_owned())
+            },
+            _ => None,
+        },
+        NodeId::RewriteRule(rule_id) => {
+            let rule = module.get_rewrite_rule(rule_id);
+            if rule.condition == Some(expr) {
+                Some("Bool".to_owned())
+            } else if rule.lhs == expr {
+                get_sort_string(context, module, rule.rhs)
+            } else {
+                get_sort_string(context, module, rule.lhs)
+            }
+        },
+        _ => None,
+    }
+}
+
+fn get_sort_strings(
+    context: &AnalysisContext,
+    module: &IrModule,
+    exprs: &[ExprId],
+) -> Option<Vec<String>> {
+    exprs.iter().map(|&expr| get_sort_string(context, module, expr)).collect()
+}
+
+fn get_sort_string(
+    context: &AnalysisContext,
+    module: &IrModule,
+    node: impl Into<NodeId>,
+) -> Option<String> {
+    let sort = query_resolved_sort(context, node).ok()?;
+    Some(ResolvedSortDisplay::new(module, &sort).to_string())
+}
+
+/// Returns an edit that adds `declaration` to the first section with the
+/// given keyword, or that adds a new section before the section that contains
+/// the token at `index` if there is no such section yet.
+fn get_declaration_edit(
+    tokens: &[Token],
+    index: usize,
+    keyword: &str,
+    declaration: &str,
+) -> TextEdit {
+    let existing_section = tokens.iter().find(|token| match token.value {
+        LexicalElement::Act => keyword == "act",
+        LexicalElement::Map => keyword == "map",
+        _ => false,
+    });
+    match existing_section {
+        Some(section) => {
+            let position = Position::new(section.loc.get_end_line(), section.loc.get_end_char());
+            TextEdit::new(Range::new(position, position), format!(" {};", declaration))
+        },
+        None => {
+            let position = Position::new(get_declaration_insert_line(tokens, index), 0);
+            let text = format!("{} {};\n\n", keyword, declaration);
+            TextEdit::new(Range::new(position, position), text)
+        },
+    }
+}
+
+/// Returns an edit that declares a variable for the `eqn` section that
+/// contains the token at `index`, which is added to the `var` section right
+/// before it if there is one.
+pub fn get_var_declaration_edit(
+    tokens: &[Token],
+    index: usize,
+    declaration: &str,
+) -> Option<TextEdit> {
+    let eqn_index = tokens[..index].iter()
+        .rposition(|token| matches!(token.value, LexicalElement::Eqn))?;
+    let previous_section = tokens[..eqn_index].iter()
+        .rev()
+        .find(|token| is_section_keyword(&token.value));
+    match previous_section {
+        Some(section) if matches!(section.value, LexicalElement::Var) => {
+            let position = Position::new(section.loc.get_end_line(), section.loc.get_end_char());
+            Some(TextEdit::new(Range::new(position, position), format!(" {};", declaration)))
+        },
+        _ => {
+            let eqn_loc = tokens[eqn_index].loc;
+            let position = Position::new(eqn_loc.get_start_line(), eqn_loc.get_start_char());
+            let text = format!("var {};\n", declaration);
+            Some(TextEdit::new(Range::new(position, position), text))
+        },
+    }
+}
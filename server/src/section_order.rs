@@ -0,0 +1,126 @@
+use crate::util::{get_byte_offset, get_end_position, is_section_keyword};
+
+use nano_crl2::core::lexer::{LexicalElement, Token};
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// A section of a model, such as a `map` section with its declarations,
+/// including the comments that directly precede it.
+struct Section<'a> {
+    rank: usize,
+    text: &'a str,
+}
+
+/// Returns the code action that sorts the sections of a model into the order
+/// `sort`, `cons`, `map`, `var` and `eqn`, `act`, `glob`, `proc`, `init`,
+/// where sections of the same kind keep their relative order.
+///
+/// Comments that precede a section without an empty line in between move
+/// along with it, while other comments before the first section stay at the
+/// top. The action is only offered if the order changes.
+pub fn get_sort_sections_actions(tokens: &[Token], input: &str, uri: &Url) -> Vec<CodeAction> {
+    let Some((header, sections)) = get_sections(tokens, input) else {
+        return Vec::new()
+    };
+    if sections.windows(2).all(|pair| pair[0].rank <= pair[1].rank) {
+        return Vec::new();
+    }
+    let mut sorted = sections.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|section| section.rank);
+
+    let mut text = String::new();
+    if !header.trim().is_empty() {
+        text.push_str(header.trim_end());
+        text.push_str("\n\n");
+    }
+    let sections = sorted.iter().map(|section| section.text.trim_end()).collect::<Vec<_>>();
+    text.push_str(&sections.join("\n\n"));
+    text.push('\n');
+
+    let range = Range::new(Position::new(0, 0), get_end_position(input));
+    vec![CodeAction {
+        title: "Sort and group sections".to_owned(),
+        kind: Some(CodeActionKind::SOURCE),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(
+            uri.clone(),
+            vec![TextEdit::new(range, text)],
+        )]))),
+        ..CodeAction::default()
+    }]
+}
+
+/// Splits the model into the text before the first section and the sections,
+/// or returns `None` if the model has no sections.
+fn get_sections<'a>(tokens: &[Token], input: &'a str) -> Option<(&'a str, Vec<Section<'a>>)> {
+    let mut starts = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let Some(rank) = get_section_rank(&token.value) else {
+            continue;
+        };
+        let start = get_attached_comments_start(tokens, index);
+        let is_line_start = start == 0 ||
+            tokens[start - 1].loc.get_end_line() < tokens[start].loc.get_start_line();
+        let loc = tokens[start].loc;
+        let character = if is_line_start { 0 } else { loc.get_start_char() };
+        starts.push((rank, get_byte_offset(input, loc.get_start_line(), character)?));
+    }
+
+    let header = &input[..starts.first()?.1];
+    let sections = starts.iter()
+        .enumerate()
+        .map(|(index, &(rank, start))| {
+            let end = starts.get(index + 1).map_or(input.len(), |&(_, end)| end);
+            Section { rank, text: &input[start..end] }
+        })
+        .collect();
+    Some((header, sections))
+}
+
+/// Returns the index of the first comment that directly precedes the section
+/// keyword at `index`, or `index` itself if there is no such comment.
+///
+/// A comment on the same line as the end of the previous section belongs to
+/// that section, and an empty line separates comments from the section.
+fn get_attached_comments_start(tokens: &[Token], index: usize) -> usize {
+    let mut start = index;
+    while start > 0 {
+        let previous = &tokens[start - 1];
+        let is_comment = matches!(
+            previous.value,
+            LexicalElement::Comment(_) | LexicalElement::DocComment(_)
+        );
+        let is_adjacent = previous.loc.get_end_line() + 1 >= tokens[start].loc.get_start_line();
+        let is_own_line = start == 1 ||
+            tokens[start - 2].loc.get_end_line() < previous.loc.get_start_line();
+        if !is_comment || !is_adjacent || !is_own_line {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+/// Returns the position of a section in the sorted model, where a `var`
+/// section shares its position with the `eqn` sections.
+fn get_section_rank(value: &LexicalElement) -> Option<usize> {
+    use LexicalElement::*;
+
+    if !is_section_keyword(value) {
+        return None;
+    }
+    let rank = match value {
+        Sort => 0,
+        Cons => 1,
+        Map => 2,
+        Var | Eqn => 3,
+        Act => 4,
+        Glob => 5,
+        Proc => 6,
+        _ => 7,
+    };
+    Some(rank)
+}
@@ -0,0 +1,364 @@
+use crate::quick_fix::get_var_declaration_edit;
+use crate::source_mapping::get_references;
+use crate::util::{
+    get_source_slice, get_token_index, lsp_range_to_source_range, range_contains, range_equals,
+    source_range_to_lsp_range,
+};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{IrDecl, IrDeclEnum};
+use nano_crl2::ir::expr::IrExprEnum;
+use nano_crl2::ir::module::{IrModule, NodeId};
+use nano_crl2::ir::sort::IrSortEnum;
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::HashMap;
+
+/// The name of an added parameter, and of the variable that takes its place
+/// in the left-hand sides of equations, before making it unique.
+const NEW_PARAM_NAME: &str = "new_param";
+/// The sort of an added parameter.
+const NEW_PARAM_SORT: &str = "Bool";
+/// The argument that is passed for an added parameter at every call site.
+const NEW_PARAM_VALUE: &str = "false";
+
+/// The parameters of a declaration or the arguments of a call, as they appear
+/// in the source.
+struct ParamList {
+    /// The ranges of the parameters or arguments.
+    items: Vec<Range>,
+    /// The range that is removed when the last item is removed, which is
+    /// where the list is inserted while it is empty.
+    outer: Range,
+    /// The text that precedes the items, such as `(`.
+    open: &'static str,
+    /// The text that follows the items, such as `)`.
+    close: &'static str,
+    /// The text between two items, such as `, `.
+    separator: &'static str,
+}
+
+impl ParamList {
+    fn add(&self, item: &str) -> TextEdit {
+        match self.items.last() {
+            Some(last) => {
+                let text = format!("{}{}", self.separator, item);
+                TextEdit::new(Range::new(last.end, last.end), text)
+            },
+            None => {
+                let text = format!("{}{}{}", self.open, item, self.close);
+                TextEdit::new(Range::new(self.outer.start, self.outer.start), text)
+            },
+        }
+    }
+
+    fn remove(&self, index: usize) -> TextEdit {
+        let range = if self.items.len() == 1 {
+            self.outer
+        } else if index + 1 < self.items.len() {
+            Range::new(self.items[index].start, self.items[index + 1].start)
+        } else {
+            Range::new(self.items[index - 1].end, self.items[index].end)
+        };
+        TextEdit::new(range, String::new())
+    }
+
+    /// Returns the edits that swap the item at `index` with the next one.
+    fn swap(&self, input: &str, index: usize) -> Vec<TextEdit> {
+        let (lhs, rhs) = (self.items[index], self.items[index + 1]);
+        let get_text = |range| get_source_slice(input, lsp_range_to_source_range(range)).to_owned();
+        vec![TextEdit::new(lhs, get_text(rhs)), TextEdit::new(rhs, get_text(lhs))]
+    }
+}
+
+/// A place where a process or map is called.
+struct CallSite {
+    args: ParamList,
+    /// The index of the first token of the call if it is the left-hand side of
+    /// an equation, which gets a variable for an added parameter.
+    lhs_index: Option<usize>,
+}
+
+/// Returns the code actions that change the parameters of the process or map
+/// whose name or parameter is at `loc`, namely:
+/// - adding a parameter at the end
+/// - removing the parameter at `loc`
+/// - moving the parameter at `loc` one place to the left or to the right
+///
+/// Every call is changed accordingly, where an added parameter is passed a
+/// placeholder value, or a new variable in the left-hand side of an equation.
+/// The actions are only offered if every use of the name is a call whose
+/// arguments can be changed, and if no call is nested in another.
+pub fn get_signature_change_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    uri: &Url,
+    loc: SourceRange,
+) -> Vec<CodeAction> {
+    let decl = module.into_iter().find_map(|node| {
+        let NodeId::Decl(decl_id) = node else {
+            return None
+        };
+        let decl = module.get_decl(decl_id);
+        let is_callable = matches!(decl.value, IrDeclEnum::Map { .. } | IrDeclEnum::Process { .. });
+        (is_callable && range_contains(module.get_node_loc(node), loc)).then_some((node, decl))
+    });
+    let Some((decl_node, decl)) = decl else {
+        return Vec::new()
+    };
+    let Some((params, labels)) = get_decl_params(module, tokens, input, decl) else {
+        return Vec::new()
+    };
+    let selected = params.items.iter()
+        .position(|&item| range_contains(lsp_range_to_source_range(item), loc));
+    if selected.is_none() && !range_contains(decl.identifier_loc, loc) {
+        return Vec::new();
+    }
+    let Some(calls) = get_call_sites(context, module, tokens, decl_node, params.items.len()) else {
+        return Vec::new()
+    };
+
+    let name = decl.identifier.to_string();
+    let mut result = Vec::new();
+    let mut add_action = |title: String, edits: Vec<TextEdit>| {
+        if let Some(edits) = check_overlap(edits) {
+            result.push(CodeAction {
+                title,
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+                ..CodeAction::default()
+            });
+        }
+    };
+
+    let new_name = get_unused_identifier(tokens, NEW_PARAM_NAME);
+    let mut edits = vec![if matches!(decl.value, IrDeclEnum::Process { .. }) {
+        params.add(&format!("{}: {}", new_name, NEW_PARAM_SORT))
+    } else {
+        params.add(NEW_PARAM_SORT)
+    }];
+    for call in &calls {
+        let Some(index) = call.lhs_index else {
+            edits.push(call.args.add(NEW_PARAM_VALUE));
+            continue;
+        };
+        edits.push(call.args.add(&new_name));
+        let declaration = format!("{}: {}", new_name, NEW_PARAM_SORT);
+        if let Some(edit) = get_var_declaration_edit(tokens, index, &declaration) {
+            if !edits.contains(&edit) {
+                edits.push(edit);
+            }
+        }
+    }
+    add_action(format!("Add parameter to `{}`", name), edits);
+
+    let Some(index) = selected else {
+        return result
+    };
+    let mut edits = vec![params.remove(index)];
+    edits.extend(calls.iter().map(|call| call.args.remove(index)));
+    add_action(format!("Remove parameter `{}` of `{}`", labels[index], name), edits);
+
+    for (other, direction) in [(index.wrapping_sub(1), "left"), (index + 1, "right")] {
+        if other >= params.items.len() {
+            continue;
+        }
+        let first = index.min(other);
+        let mut edits = params.swap(input, first);
+        for call in &calls {
+            edits.extend(call.args.swap(input, first));
+        }
+        let label = &labels[index];
+        add_action(format!("Move parameter `{}` of `{}` to the {}", label, name, direction), edits);
+    }
+    result
+}
+
+/// Returns the parameters of a process or map declaration, along with a label
+/// for each of them, which is its name for a process and its sort for a map.
+///
+/// Returns `None` if the parameters cannot be changed separately, such as when
+/// process parameters share their sort (as in `P(x, y: Nat)`) or when a map
+/// declaration declares multiple names.
+fn get_decl_params(
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    decl: &IrDecl,
+) -> Option<(ParamList, Vec<String>)> {
+    let identifier_end = source_range_to_lsp_range(decl.identifier_loc).end;
+    match &decl.value {
+        IrDeclEnum::Process { params, .. } => {
+            let items = params.iter()
+                .map(|&param| source_range_to_lsp_range(module.get_node_loc(NodeId::Param(param))))
+                .collect::<Vec<Range>>();
+            if items.iter().any(|&item| {
+                !get_source_slice(input, lsp_range_to_source_range(item)).contains(':')
+            }) {
+                return None;
+            }
+            let outer_end = match items.last() {
+                Some(last) => {
+                    let last = lsp_range_to_source_range(*last);
+                    let index = get_token_index(tokens, last)?;
+                    // skip the parentheses of the sort, as in `xs: List(Nat)`
+                    let mut depth = 0usize;
+                    let closing_paren = tokens[index..].iter().find(|token| {
+                        match token.value {
+                            LexicalElement::OpeningParen => depth += 1,
+                            LexicalElement::ClosingParen if depth == 0 => return true,
+                            LexicalElement::ClosingParen => depth -= 1,
+                            _ => {},
+                        }
+                        false
+                    })?;
+                    source_range_to_lsp_range(closing_paren.loc).end
+                },
+                None => identifier_end,
+            };
+            let labels = params.iter()
+                .map(|&param| module.get_param(param).identifier.to_string())
+                .collect();
+            let list = ParamList {
+                items,
+                outer: Range::new(identifier_end, outer_end),
+                open: "(",
+                close: ")",
+                separator: ", ",
+            };
+            Some((list, labels))
+        },
+        IrDeclEnum::Map { sort } => {
+            let is_shared = module.into_iter().any(|node| match node {
+                NodeId::Decl(other) => {
+                    let other = module.get_decl(other);
+                    other.def_id != decl.def_id &&
+                        matches!(other.value, IrDeclEnum::Map { sort: other } if other == *sort)
+                },
+                _ => false,
+            });
+            if is_shared {
+                return None;
+            }
+            let sort_loc = source_range_to_lsp_range(module.get_sort(*sort).loc);
+            let (items, outer) = match &module.get_sort(*sort).value {
+                IrSortEnum::Function { lhs, rhs } => {
+                    let items = lhs.iter()
+                        .map(|&param| source_range_to_lsp_range(module.get_sort(param).loc))
+                        .collect::<Vec<Range>>();
+                    let rhs_start = source_range_to_lsp_range(module.get_sort(*rhs).loc).start;
+                    let outer = Range::new(items.first()?.start, rhs_start);
+                    (items, outer)
+                },
+                _ => (Vec::new(), Range::new(sort_loc.start, sort_loc.start)),
+            };
+            let labels = items.iter()
+                .map(|&item| get_source_slice(input, lsp_range_to_source_range(item)).to_owned())
+                .collect();
+            let list = ParamList { items, outer, open: "", close: " -> ", separator: " # " };
+            Some((list, labels))
+        },
+        _ => None,
+    }
+}
+
+/// Returns the calls of the declared process or map, or `None` if it is used
+/// in a way other than being called with `param_count` arguments, such as
+/// when a map is passed to another map.
+fn get_call_sites(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    decl_node: NodeId,
+    param_count: usize,
+) -> Option<Vec<CallSite>> {
+    let references = get_references(context, module, decl_node).ok()?;
+    references.into_iter()
+        .map(|reference| {
+            let name_node = module.into_iter().find(|&node| match node {
+                NodeId::Action(action_id) => {
+                    range_equals(module.get_action(action_id).identifier_loc, reference)
+                },
+                NodeId::Expr(expr_id) => {
+                    matches!(module.get_expr(expr_id).value, IrExprEnum::Name { .. }) &&
+                        range_equals(module.get_node_loc(node), reference)
+                },
+                _ => false,
+            })?;
+            let name_end = source_range_to_lsp_range(reference).end;
+
+            let (call, args) = match name_node {
+                NodeId::Action(action_id) => (name_node, module.get_action(action_id).args.clone()),
+                NodeId::Expr(expr_id) => match module.get_parent(name_node) {
+                    Some(parent @ NodeId::Expr(parent_id)) => {
+                        match &module.get_expr(parent_id).value {
+                            IrExprEnum::Apply { callee, args } if *callee == expr_id => {
+                                (parent, args.clone())
+                            },
+                            _ => (name_node, Vec::new()),
+                        }
+                    },
+                    _ => (name_node, Vec::new()),
+                },
+                _ => return None,
+            };
+            if args.len() != param_count {
+                return None;
+            }
+
+            let lhs_index = match module.get_parent(call) {
+                Some(NodeId::RewriteRule(rule_id))
+                    if NodeId::Expr(module.get_rewrite_rule(rule_id).lhs) == call =>
+                {
+                    get_token_index(tokens, module.get_node_loc(call))
+                },
+                _ => None,
+            };
+            let items = args.iter()
+                .map(|&arg| source_range_to_lsp_range(module.get_node_loc(NodeId::Expr(arg))))
+                .collect();
+            let call_end = source_range_to_lsp_range(module.get_node_loc(call)).end;
+            let args = ParamList {
+                items,
+                outer: Range::new(name_end, call_end),
+                open: "(",
+                close: ")",
+                separator: ", ",
+            };
+            Some(CallSite { args, lhs_index })
+        })
+        .collect()
+}
+
+/// Returns the edits sorted by position, or `None` if two of them overlap,
+/// which happens when a call is nested in the arguments of another.
+fn check_overlap(mut edits: Vec<TextEdit>) -> Option<Vec<TextEdit>> {
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    let overlaps = edits.windows(2).any(|pair| pair[0].range.end > pair[1].range.start);
+    (!overlaps).then_some(edits)
+}
+
+/// Returns `name`, followed by a number if that is needed to make it differ
+/// from all identifiers in the model.
+fn get_unused_identifier(tokens: &[Token], name: &str) -> String {
+    let is_used = |candidate: &str| {
+        tokens.iter().any(|token| match &token.value {
+            LexicalElement::Identifier(identifier) => identifier == candidate,
+            _ => false,
+        })
+    };
+    let mut result = name.to_owned();
+    let mut counter = 1;
+    while is_used(&result) {
+        counter += 1;
+        result = format!("{}{}", name, counter);
+    }
+    result
+}
@@ -0,0 +1,199 @@
+use crate::documentation::get_doc_comment;
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::sort_resolution::{
+    get_decl_sort, query_resolved_sort,
+};
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::display::ResolvedSortDisplay;
+use nano_crl2::ir::iterator::{DefiningNode, get_def_data, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, SortId};
+use nano_crl2::ir::sort::IrSortEnum;
+
+use tower_lsp::lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation,
+    ParameterLabel, Position, SignatureInformation,
+};
+
+/// Finds the call (e.g. `f(x, y)` or `Main(0, 1)`) that `position` is inside
+/// of, by scanning backwards through the tokens for an unclosed parenthesis.
+///
+/// Returns the index of the token of the called identifier and the index of
+/// the argument that `position` is in, or `None` if `position` is not inside
+/// the arguments of a call.
+pub fn get_call_at_position(tokens: &[Token], position: Position) -> Option<(usize, u32)> {
+    let end = tokens.iter()
+        .position(|token| {
+            (token.loc.get_end_line(), token.loc.get_end_char()) >
+                (position.line, position.character)
+        })
+        .unwrap_or(tokens.len());
+
+    let mut depth = 0usize;
+    let mut argument_index = 0;
+    for index in (0..end).rev() {
+        use LexicalElement::*;
+
+        match &tokens[index].value {
+            ClosingParen | ClosingBracket | ClosingBrace => depth += 1,
+            OpeningParen if depth == 0 => {
+                return match index.checked_sub(1).map(|i| &tokens[i].value) {
+                    Some(Identifier(_)) => Some((index - 1, argument_index)),
+                    _ => None,
+                };
+            },
+            OpeningBracket | OpeningBrace if depth == 0 => return None,
+            OpeningParen | OpeningBracket | OpeningBrace => depth -= 1,
+            Comma if depth == 0 => argument_index += 1,
+            Semicolon | Act | Cons | Eqn | Glob | Init | Map | Proc | Sort | Var => {
+                return None
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Returns the signature of a callable definition, i.e. a process, an action
+/// or something with a function sort, or `None` if `def` cannot be called.
+///
+/// For instance, for `map f: Nat # Nat -> Bool` this returns a signature with
+/// the label `f(Nat, Nat): Bool`.
+pub fn get_signature_information(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    input: &str,
+    def: DefId,
+) -> Option<SignatureInformation> {
+    let (_, identifier, identifier_loc) = get_def_data(module, module.get_def_source(def))?;
+
+    let (parameters, result) = match get_defining_node_from_def(module, def) {
+        DefiningNode::Decl(decl) => match &decl.value {
+            IrDeclEnum::Process { params, .. } => {
+                let parameters = params.iter()
+                    .map(|&param_id| {
+                        let param = module.get_param(param_id);
+                        match query_resolved_sort(context, param.sort) {
+                            Ok(sort) => format!(
+                                "{}: {}",
+                                param.identifier,
+                                ResolvedSortDisplay::new(module, &sort),
+                            ),
+                            Err(()) => param.identifier.to_string(),
+                        }
+                    })
+                    .collect();
+                (parameters, None)
+            },
+            IrDeclEnum::Action { params } => {
+                let parameters = params.iter()
+                    .map(|&sort| {
+                        let sort = query_resolved_sort(context, sort).ok()?;
+                        Some(ResolvedSortDisplay::new(module, &sort).to_string())
+                    })
+                    .collect::<Option<_>>()?;
+                (parameters, None)
+            },
+            _ => split_function_sort(context, module, get_decl_sort(decl)?)
+                .map(|(parameters, result)| (parameters, Some(result)))?,
+        },
+        _ => split_function_sort(context, module, get_declared_sort(module, def)?)
+            .map(|(parameters, result)| (parameters, Some(result)))?,
+    };
+
+    let mut label = format!("{}(", identifier);
+    let mut parameter_information = Vec::new();
+    for (index, parameter) in parameters.iter().enumerate() {
+        if index > 0 {
+            label.push_str(", ");
+        }
+        let start = label.encode_utf16().count() as u32;
+        label.push_str(parameter);
+        let end = label.encode_utf16().count() as u32;
+        parameter_information.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: None,
+        });
+    }
+    label.push(')');
+    if let Some(result) = result {
+        label.push_str(": ");
+        label.push_str(&result);
+    }
+
+    let documentation = get_doc_comment(tokens, input, identifier_loc)
+        .map(|doc_comment| Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: doc_comment,
+        }));
+
+    Some(SignatureInformation {
+        label,
+        documentation,
+        parameters: Some(parameter_information),
+        active_parameter: None,
+    })
+}
+
+/// Returns the sort that `def` is declared with, or `None` if it is declared
+/// without one, such as a process.
+pub fn get_declared_sort(module: &IrModule, def: DefId) -> Option<SortId> {
+    match get_defining_node_from_def(module, def) {
+        DefiningNode::Decl(decl) => get_decl_sort(decl),
+        DefiningNode::BinderExpr { sort, .. } | DefiningNode::SumProc { sort, .. } => Some(sort),
+        DefiningNode::Param(param) => Some(param.sort),
+        DefiningNode::RewriteVar(rewrite_var) => Some(rewrite_var.sort),
+    }
+}
+
+/// Splits a function sort such as `Nat # Nat -> Bool` into the resolved
+/// sorts of its parameters and its result, or returns `None` if `sort` is not
+/// a function sort.
+pub fn split_function_sort(
+    context: &AnalysisContext,
+    module: &IrModule,
+    sort: SortId,
+) -> Option<(Vec<String>, String)> {
+    let IrSortEnum::Function { lhs, rhs } = &module.get_sort(sort).value else {
+        return None
+    };
+    let display = |sort: SortId| {
+        let sort = query_resolved_sort(context, sort).ok()?;
+        Some(ResolvedSortDisplay::new(module, &sort).to_string())
+    };
+    let parameters = lhs.iter().map(|&param| display(param)).collect::<Option<_>>()?;
+    Some((parameters, display(*rhs)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nano_crl2::analysis::context::AnalysisContext;
+    use nano_crl2::analysis::parsing::query_token_list;
+
+    fn get_call(input: &str, line: u32, character: u32) -> Option<(usize, u32)> {
+        let mut context = AnalysisContext::new();
+        let module_id = context.add_model_input("test.mcrl2".to_owned(), input.to_owned());
+        let tokens = query_token_list(&context, module_id).unwrap().to_vec();
+        get_call_at_position(&tokens, Position::new(line, character))
+    }
+
+    #[test]
+    fn get_call_at_position_counts_arguments() {
+        let input = "init f(1, g(2, 3), 4);";
+        assert_eq!(get_call(input, 0, 7), Some((1, 0)));
+        assert_eq!(get_call(input, 0, 14), Some((5, 1)));
+        assert_eq!(get_call(input, 0, 17), Some((1, 1)));
+        assert_eq!(get_call(input, 0, 20), Some((1, 2)));
+    }
+
+    #[test]
+    fn get_call_at_position_ignores_other_brackets() {
+        assert_eq!(get_call("init f([1, 2]);", 0, 10), None);
+        assert_eq!(get_call("init (1 + 2);", 0, 7), None);
+        assert_eq!(get_call("map f: Nat;\ninit g(1);", 1, 0), None);
+    }
+}
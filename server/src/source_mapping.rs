@@ -1,10 +1,13 @@
 
-use nano_crl2::analysis::semantic::name_resolution::{get_defs_in_context, NameLookup, NameLookupEnum};
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::analysis::semantic::name_resolution::{
+    get_defs_in_context, query_def_of_name, NameLookup, NameLookupEnum,
+};
 use nano_crl2::core::syntax::{Identifier, SourceCursorPos, SourceRange};
 use nano_crl2::ir::decl::DefId;
 use nano_crl2::ir::expr::IrExprEnum;
-use nano_crl2::ir::iterator::{IrIterator, ParentIterator};
-use nano_crl2::ir::module::{IrModule, NodeId};
+use nano_crl2::ir::iterator::{get_def_data, IrIterator, ParentIterator};
+use nano_crl2::ir::module::{ExprId, IrModule, NodeId};
 use nano_crl2::ir::proc::IrProcEnum;
 use nano_crl2::ir::sort::IrSortEnum;
 
@@ -196,3 +199,55 @@ pub fn get_def_context_at_loc(
     }
     Ok(result)
 }
+
+/// Returns the name at the head of an expression, i.e. the `f` in `f(x)(y)`,
+/// or `None` if the expression is not a (possibly applied) name.
+///
+/// This is mostly useful for finding the map that an equation defines.
+pub fn get_head_symbol(ir_module: &IrModule, expr: ExprId) -> Option<ExprId> {
+    let mut current = expr;
+    loop {
+        match &ir_module.get_expr(current).value {
+            IrExprEnum::Apply { callee, .. } => current = *callee,
+            IrExprEnum::Name { .. } => return Some(current),
+            _ => return None,
+        }
+    }
+}
+
+/// Returns the source locations of all names that refer to the definition
+/// that is introduced by `source_id`.
+///
+/// Only the part of the module in which the definition is visible is
+/// searched, i.e. the parent of a declaration, parameter or rewrite variable,
+/// or the binder (or `sum`) itself.
+pub fn get_references(
+    context: &AnalysisContext,
+    ir_module: &IrModule,
+    source_id: NodeId,
+) -> Result<Vec<SourceRange>, ()> {
+    let source_identifier = get_def_data(ir_module, source_id).unwrap().1;
+
+    let start_node = if matches!(source_id, NodeId::Decl(..) | NodeId::Param(..) | NodeId::RewriteVar(..)) {
+        let Some(parent) = ir_module.get_parent(source_id) else {
+            return Ok(Vec::new())
+        };
+        parent
+    } else {
+        source_id
+    };
+
+    let mut result = Vec::new();
+    let iterator = IdentifierIterator::new(ir_module, start_node);
+    for (identifier, loc, target, def_id) in iterator {
+        if def_id.is_some() || identifier != source_identifier {
+            continue; // easy optimization
+        }
+        let def = query_def_of_name(context, target)?;
+        let def_source = ir_module.get_def_source(def);
+        if def_source == source_id {
+            result.push(loc);
+        }
+    }
+    Ok(result)
+}
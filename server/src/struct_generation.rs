@@ -0,0 +1,331 @@
+use crate::signature_help::split_function_sort;
+use crate::struct_sort::get_struct_constructors;
+use crate::util::{is_section_keyword, range_contains};
+
+use nano_crl2::analysis::context::AnalysisContext;
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId};
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use std::collections::{HashMap, HashSet};
+
+/// A constructor of a struct sort, with the sorts of its parameters.
+struct Constructor {
+    name: String,
+    param_sorts: Vec<String>,
+}
+
+/// Returns the code actions that generate maps with equations for the struct
+/// sort that is declared at `loc`, namely:
+/// - a map that checks whether two terms have the same constructor
+/// - a map that computes the size of a term, i.e. the number of constructors
+///   that it consists of
+/// - a predicate with one equation per constructor, to be filled in
+///
+/// The generated code is added after the last declaration of the `sort`
+/// section.
+pub fn get_struct_generation_actions(
+    context: &AnalysisContext,
+    module: &IrModule,
+    tokens: &[Token],
+    uri: &Url,
+    loc: SourceRange,
+) -> Vec<CodeAction> {
+    let sort_decl = module.into_iter().find_map(|node| {
+        let NodeId::Decl(decl_id) = node else {
+            return None
+        };
+        let decl = module.get_decl(decl_id);
+        let is_struct = matches!(decl.value, IrDeclEnum::SortAlias { .. }) &&
+            !get_struct_constructors(module, decl.def_id).is_empty();
+        (is_struct && range_contains(module.get_node_loc(node), loc)).then_some(decl)
+    });
+    let Some(sort_decl) = sort_decl else {
+        return Vec::new()
+    };
+    let Some(constructors) = get_constructors(context, module, sort_decl.def_id) else {
+        return Vec::new()
+    };
+
+    let sort_name = sort_decl.identifier.to_string();
+    let mut chars = sort_name.chars();
+    let name_prefix = chars.next()
+        .into_iter()
+        .flat_map(char::to_lowercase)
+        .chain(chars)
+        .collect::<String>();
+    let used_names = module.into_iter()
+        .filter_map(|node| match node {
+            NodeId::Decl(decl_id) => Some(module.get_decl(decl_id).identifier.to_string()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let variables = Variables::new(&constructors, &used_names);
+
+    let Some(range) = get_insert_range(tokens, sort_decl.identifier_loc) else {
+        return Vec::new()
+    };
+    let create_action = |title: String, text: String| CodeAction {
+        title,
+        kind: Some(CodeActionKind::REFACTOR),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(
+            uri.clone(),
+            vec![TextEdit::new(range, format!("\n\n{}\n", text.trim_end()))],
+        )]))),
+        ..CodeAction::default()
+    };
+
+    let mut result = Vec::new();
+
+    let map_name = get_unused_name(format!("{}_same_constructor", name_prefix), &used_names);
+    let mut equations = Vec::new();
+    for (i, lhs) in constructors.iter().enumerate() {
+        for (j, rhs) in constructors.iter().enumerate() {
+            equations.push(format!(
+                "{}({}, {}) = {}",
+                map_name,
+                variables.apply(lhs, 0),
+                variables.apply(rhs, 1),
+                i == j,
+            ));
+        }
+    }
+    result.push(create_action(
+        format!("Generate map `{}` for `{}`", map_name, sort_name),
+        format_declarations(
+            &format!("{}: {} # {} -> Bool", map_name, sort_name, sort_name),
+            &variables.get_declarations(2),
+            &equations,
+        ),
+    ));
+
+    let map_name = get_unused_name(format!("{}_size", name_prefix), &used_names);
+    let equations = constructors.iter()
+        .map(|constructor| {
+            let mut sizes = vec!["1".to_owned()];
+            for (index, sort) in constructor.param_sorts.iter().enumerate() {
+                if *sort == sort_name {
+                    sizes.push(format!("{}({})", map_name, variables.get(constructor, index, 0)));
+                }
+            }
+            format!("{}({}) = {}", map_name, variables.apply(constructor, 0), sizes.join(" + "))
+        })
+        .collect::<Vec<_>>();
+    result.push(create_action(
+        format!("Generate map `{}` for `{}`", map_name, sort_name),
+        format_declarations(
+            &format!("{}: {} -> Nat", map_name, sort_name),
+            &variables.get_declarations(1),
+            &equations,
+        ),
+    ));
+
+    let map_name = get_unused_name(format!("{}_predicate", name_prefix), &used_names);
+    let equations = constructors.iter()
+        .map(|constructor| format!("{}({}) = false", map_name, variables.apply(constructor, 0)))
+        .collect::<Vec<_>>();
+    result.push(create_action(
+        format!("Generate predicate `{}` on `{}`", map_name, sort_name),
+        format_declarations(
+            &format!("{}: {} -> Bool", map_name, sort_name),
+            &variables.get_declarations(1),
+            &equations,
+        ),
+    ));
+
+    result
+}
+
+/// The variables that are used as the arguments of the constructors in the
+/// generated equations.
+///
+/// Each side of an equation gets its own set of variables, where each set
+/// has enough variables of each sort for every constructor.
+struct Variables {
+    /// The sorts of the variables, in the order in which they are declared.
+    sorts: Vec<String>,
+    /// The names of the variables of each side.
+    names: Vec<Vec<String>>,
+    /// For each constructor, the index of the variable for each parameter.
+    indices: HashMap<String, Vec<usize>>,
+}
+
+impl Variables {
+    fn new(constructors: &[Constructor], used_names: &HashSet<String>) -> Self {
+        let mut sorts: Vec<String> = Vec::new();
+        let mut indices = HashMap::new();
+        for constructor in constructors {
+            let mut constructor_indices = Vec::new();
+            for (index, sort) in constructor.param_sorts.iter().enumerate() {
+                // reuse the variables of this sort that earlier parameters of
+                // this constructor do not use yet
+                let occurrence = constructor.param_sorts[..index].iter()
+                    .filter(|other| *other == sort)
+                    .count();
+                let variable = sorts.iter()
+                    .enumerate()
+                    .filter(|(_, other)| *other == sort)
+                    .nth(occurrence)
+                    .map(|(variable, _)| variable);
+                constructor_indices.push(variable.unwrap_or_else(|| {
+                    sorts.push(sort.clone());
+                    sorts.len() - 1
+                }));
+            }
+            indices.insert(constructor.name.clone(), constructor_indices);
+        }
+
+        let names = ["x", "y"].iter()
+            .map(|prefix| {
+                (1..=sorts.len())
+                    .map(|number| get_unused_name(format!("{}{}", prefix, number), used_names))
+                    .collect()
+            })
+            .collect();
+        Variables { sorts, names, indices }
+    }
+
+    fn get(&self, constructor: &Constructor, param: usize, side: usize) -> &str {
+        &self.names[side][self.indices[&constructor.name][param]]
+    }
+
+    /// Returns the constructor applied to the variables of the given side,
+    /// e.g. `cons(x1, x2)`, or just the constructor if it has no parameters.
+    fn apply(&self, constructor: &Constructor, side: usize) -> String {
+        if constructor.param_sorts.is_empty() {
+            return constructor.name.clone();
+        }
+        let args = (0..constructor.param_sorts.len())
+            .map(|param| self.get(constructor, param, side))
+            .collect::<Vec<_>>();
+        format!("{}({})", constructor.name, args.join(", "))
+    }
+
+    /// Returns the declarations of the variables of the first `side_count`
+    /// sides, e.g. `x1: Nat`.
+    fn get_declarations(&self, side_count: usize) -> Vec<String> {
+        self.names[..side_count].iter()
+            .flat_map(|names| {
+                names.iter()
+                    .zip(&self.sorts)
+                    .map(|(name, sort)| format!("{}: {}", name, sort))
+            })
+            .collect()
+    }
+}
+
+/// Returns the constructors of a struct sort, or `None` if the sort of one of
+/// them could not be determined.
+fn get_constructors(
+    context: &AnalysisContext,
+    module: &IrModule,
+    sort: DefId,
+) -> Option<Vec<Constructor>> {
+    get_struct_constructors(module, sort)
+        .into_iter()
+        .map(|def_id| {
+            let DefiningNode::Decl(decl) = get_defining_node_from_def(module, def_id) else {
+                return None
+            };
+            let IrDeclEnum::Constructor { sort } = &decl.value else {
+                return None
+            };
+            let param_sorts = split_function_sort(context, module, *sort)
+                .map_or(Vec::new(), |(params, _)| params);
+            Some(Constructor { name: decl.identifier.to_string(), param_sorts })
+        })
+        .collect()
+}
+
+/// Returns the range that is replaced by code that belongs after the section
+/// that contains `loc`.
+///
+/// This starts after the `;` of the last declaration in that section (and a
+/// comment behind it), and covers the spaces up to the next token if it is on
+/// the same line, so that the code can be put on lines of its own.
+fn get_insert_range(tokens: &[Token], loc: SourceRange) -> Option<Range> {
+    let start_index = tokens.iter().position(|token| {
+        (token.loc.get_start_line(), token.loc.get_start_char()) >
+            (loc.get_end_line(), loc.get_end_char())
+    })?;
+    let end_index = tokens[start_index..].iter()
+        .position(|token| is_section_keyword(&token.value))
+        .map_or(tokens.len(), |offset| start_index + offset);
+    let mut index = (start_index..end_index)
+        .rev()
+        .find(|&index| matches!(tokens[index].value, LexicalElement::Semicolon))?;
+    while let Some(next) = tokens.get(index + 1) {
+        let is_trailing_comment = matches!(next.value, LexicalElement::Comment(_)) &&
+            next.loc.get_start_line() == tokens[index].loc.get_end_line();
+        if !is_trailing_comment {
+            break;
+        }
+        index += 1;
+    }
+
+    let start = Position::new(tokens[index].loc.get_end_line(), tokens[index].loc.get_end_char());
+    let end = match tokens.get(index + 1) {
+        Some(next) if next.loc.get_start_line() == start.line => {
+            Position::new(next.loc.get_start_line(), next.loc.get_start_char())
+        },
+        _ => start,
+    };
+    Some(Range::new(start, end))
+}
+
+/// Appends underscores to `name` until it differs from all declared names.
+fn get_unused_name(mut name: String, used_names: &HashSet<String>) -> String {
+    while used_names.contains(&name) {
+        name.push('_');
+    }
+    name
+}
+
+/// Formats a map declaration with its variables and equations, followed by an
+/// empty line.
+pub fn format_declarations(map: &str, variables: &[String], equations: &[String]) -> String {
+    let mut result = format!("map {};\n", map);
+    for (index, variable) in variables.iter().enumerate() {
+        let keyword = if index == 0 { "var" } else { "   " };
+        result.push_str(&format!("{} {};\n", keyword, variable));
+    }
+    for (index, equation) in equations.iter().enumerate() {
+        let keyword = if index == 0 { "eqn" } else { "   " };
+        result.push_str(&format!("{} {};\n", keyword, equation));
+    }
+    result.push('\n');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nano_crl2::analysis::context::AnalysisContext;
+    use nano_crl2::analysis::parsing::query_token_list;
+
+    fn get_range(input: &str, loc: SourceRange) -> Option<Range> {
+        let mut context = AnalysisContext::new();
+        let module_id = context.add_model_input("test.mcrl2".to_owned(), input.to_owned());
+        get_insert_range(&query_token_list(&context, module_id).unwrap(), loc)
+    }
+
+    #[test]
+    fn insert_range_follows_declaration_on_the_same_line() {
+        let range = get_range("sort S = struct a | b; map f: S;", SourceRange::new(0, 5, 0, 6));
+        assert_eq!(range, Some(Range::new(Position::new(0, 22), Position::new(0, 23))));
+    }
+
+    #[test]
+    fn insert_range_skips_later_declarations_and_comments() {
+        let input = "sort S = struct a | b;\n     T = S; % alias\nmap f: S;\n";
+        let range = get_range(input, SourceRange::new(0, 5, 0, 6));
+        assert_eq!(range, Some(Range::new(Position::new(1, 19), Position::new(1, 19))));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::util::range_contains;
+
+use nano_crl2::ir::decl::{DefId, IrDeclEnum};
+use nano_crl2::ir::iterator::{DefiningNode, get_defining_node_from_def};
+use nano_crl2::ir::module::{IrModule, NodeId};
+
+/// Returns the constructors of a struct sort, in the order in which they are
+/// declared, or an empty list if `def` is not a struct sort.
+///
+/// These are the constructor declarations that lie inside the declaration of
+/// the sort.
+pub fn get_struct_constructors(module: &IrModule, def: DefId) -> Vec<DefId> {
+    let sort_node = module.get_def_source(def);
+    let DefiningNode::Decl(sort_decl) = get_defining_node_from_def(module, def) else {
+        return Vec::new()
+    };
+    if !matches!(sort_decl.value, IrDeclEnum::SortAlias { .. }) {
+        return Vec::new();
+    }
+
+    let sort_loc = module.get_node_loc(sort_node);
+    let mut result = Vec::new();
+    for node in module {
+        let NodeId::Decl(decl_id) = node else {
+            continue;
+        };
+        let decl = module.get_decl(decl_id);
+        if matches!(decl.value, IrDeclEnum::Constructor { .. }) &&
+            range_contains(sort_loc, decl.identifier_loc)
+        {
+            result.push(decl.def_id);
+        }
+    }
+    result
+}
+
+/// Returns the struct sort that declares the given constructor, or `None` if
+/// it is not declared by a struct sort (e.g. if it is declared in a `cons`
+/// section).
+pub fn get_struct_sort_of_constructor(module: &IrModule, constructor: DefId) -> Option<DefId> {
+    let DefiningNode::Decl(constructor_decl) = get_defining_node_from_def(module, constructor) else {
+        return None
+    };
+    for node in module {
+        let NodeId::Decl(decl_id) = node else {
+            continue;
+        };
+        let decl = module.get_decl(decl_id);
+        if matches!(decl.value, IrDeclEnum::SortAlias { .. }) &&
+            range_contains(module.get_node_loc(node), constructor_decl.identifier_loc)
+        {
+            return Some(decl.def_id);
+        }
+    }
+    None
+}
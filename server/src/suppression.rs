@@ -0,0 +1,160 @@
+use crate::diagnostic_code::DiagnosticKind;
+use crate::util::{get_source_slice, is_section_keyword, range_contains};
+
+use nano_crl2::core::lexer::{LexicalElement, Token};
+use nano_crl2::core::syntax::SourceRange;
+
+/// The warnings that are suppressed by comment pragmas in a file.
+///
+/// A pragma is a comment of the form `% nano-crl2: allow(unused, shadowing)`,
+/// where each name is either the name or the code of a kind of diagnostic
+/// (e.g. `unused` or `W0001`). It applies to the declaration that follows it,
+/// or to the whole section if it is placed before a section keyword such as
+/// `map`. A pragma at the top of the file, before anything but comments and
+/// followed by a blank line, applies to the whole file instead, as does a
+/// pragma of the form `% nano-crl2: allow-file(unused)` wherever it is placed.
+pub struct Suppressions {
+    file_wide: Vec<String>,
+    scoped: Vec<(SourceRange, Vec<String>)>,
+}
+
+impl Suppressions {
+    pub fn from_tokens(tokens: &[Token], input: &str) -> Self {
+        use LexicalElement::{Comment, DocComment};
+
+        let mut file_wide = Vec::new();
+        let mut scoped = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            if !matches!(token.value, Comment(_)) {
+                continue;
+            }
+            let comment = get_source_slice(input, token.loc);
+            let Some((is_file_wide, names)) = parse_pragma(comment) else {
+                continue;
+            };
+
+            let is_at_top = tokens[..index].iter()
+                .all(|token| matches!(token.value, Comment(_) | DocComment(_)));
+            let is_followed_by_blank_line = match tokens.get(index + 1) {
+                Some(next) => next.loc.get_start_line() > token.loc.get_end_line() + 1,
+                None => true,
+            };
+            if is_file_wide || (is_at_top && is_followed_by_blank_line) {
+                file_wide.extend(names);
+            } else if let Some(loc) = get_suppressed_range(tokens, index) {
+                scoped.push((loc, names));
+            }
+        }
+        Suppressions { file_wide, scoped }
+    }
+
+    /// Returns whether a diagnostic of the given kind at the given location is
+    /// suppressed by some pragma.
+    pub fn is_suppressed(&self, kind: &DiagnosticKind, loc: SourceRange) -> bool {
+        let matches_kind = |name: &String| {
+            name == kind.name || name.eq_ignore_ascii_case(kind.code)
+        };
+        self.file_wide.iter().any(matches_kind) ||
+            self.scoped.iter().any(|(scope, names)| {
+                range_contains(*scope, loc) && names.iter().any(matches_kind)
+            })
+    }
+}
+
+/// Parses a comment such as `% nano-crl2: allow(unused)`, returning whether
+/// it is an `allow-file` pragma and the names inside the parentheses, or
+/// `None` if the comment is not a pragma.
+fn parse_pragma(comment: &str) -> Option<(bool, Vec<String>)> {
+    let text = comment.trim_start_matches('%').trim();
+    let text = text.strip_prefix("nano-crl2:")?.trim();
+    let (is_file_wide, text) = match text.strip_prefix("allow-file") {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix("allow")?),
+    };
+    let text = text.trim_start().strip_prefix('(')?;
+    let end = text.find(')')?;
+    let names = text[..end]
+        .split(',')
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect();
+    Some((is_file_wide, names))
+}
+
+/// Returns the range of the declaration (or section) that follows the pragma
+/// at the given token index.
+fn get_suppressed_range(tokens: &[Token], pragma_index: usize) -> Option<SourceRange> {
+    use LexicalElement::*;
+
+    let start_index = (pragma_index + 1..tokens.len())
+        .find(|&i| !matches!(tokens[i].value, Comment(_) | DocComment(_)))?;
+    let is_section = is_section_keyword(&tokens[start_index].value);
+
+    let mut end_index = start_index;
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate().skip(start_index) {
+        match token.value {
+            OpeningParen | OpeningBracket | OpeningBrace => depth += 1,
+            ClosingParen | ClosingBracket | ClosingBrace => depth = depth.saturating_sub(1),
+            Semicolon if depth == 0 && !is_section => {
+                end_index = index;
+                break;
+            },
+            _ if is_section && index > start_index && is_section_keyword(&token.value) => break,
+            _ => {},
+        }
+        end_index = index;
+    }
+
+    let start = tokens[start_index].loc;
+    let end = tokens[end_index].loc;
+    Some(SourceRange::new(
+        start.get_start_line(),
+        start.get_start_char(),
+        end.get_end_line(),
+        end.get_end_char(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::diagnostic_code::{SHADOWING, UNUSED};
+
+    use nano_crl2::analysis::context::AnalysisContext;
+    use nano_crl2::analysis::parsing::query_token_list;
+
+    fn get_suppressions(input: &str) -> Suppressions {
+        let mut context = AnalysisContext::new();
+        let module_id = context.add_model_input("test.mcrl2".to_owned(), input.to_owned());
+        let tokens = query_token_list(&context, module_id).unwrap();
+        Suppressions::from_tokens(&tokens, input)
+    }
+
+    #[test]
+    fn pragma_at_top_followed_by_blank_line_applies_to_file() {
+        let suppressions = get_suppressions(
+            "% nano-crl2: allow(unused)\n\nmap f: Nat;\nmap g: Nat;\n",
+        );
+        assert!(suppressions.is_suppressed(&UNUSED, SourceRange::new(3, 4, 3, 5)));
+        assert!(!suppressions.is_suppressed(&SHADOWING, SourceRange::new(3, 4, 3, 5)));
+    }
+
+    #[test]
+    fn pragma_before_declaration_applies_to_declaration() {
+        let suppressions = get_suppressions(
+            "% nano-crl2: allow(W0001)\nmap f: Nat;\nmap g: Nat;\n",
+        );
+        assert!(suppressions.is_suppressed(&UNUSED, SourceRange::new(1, 4, 1, 5)));
+        assert!(!suppressions.is_suppressed(&UNUSED, SourceRange::new(2, 4, 2, 5)));
+    }
+
+    #[test]
+    fn allow_file_pragma_applies_to_file() {
+        let suppressions = get_suppressions(
+            "map f: Nat;\n% nano-crl2: allow-file(unused)\nmap g: Nat;\n",
+        );
+        assert!(suppressions.is_suppressed(&UNUSED, SourceRange::new(0, 4, 0, 5)));
+    }
+}
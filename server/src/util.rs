@@ -1,4 +1,5 @@
 
+use nano_crl2::core::lexer::{LexicalElement, Token};
 use nano_crl2::core::syntax::SourceRange;
 
 use tower_lsp::lsp_types::{Position, Range};
@@ -18,3 +19,196 @@ pub fn source_range_to_lsp_range(range: SourceRange) -> Range {
         Position::new(range.get_end_line(), range.get_end_char()),
     )
 }
+
+/// Returns the index of the token that starts at the start of `loc`, or `None`
+/// if there is no such token.
+pub fn get_token_index(tokens: &[Token], loc: SourceRange) -> Option<usize> {
+    tokens.iter().position(|token| {
+        token.loc.get_start_line() == loc.get_start_line() &&
+            token.loc.get_start_char() == loc.get_start_char()
+    })
+}
+
+/// Returns whether a token starts a section of a model, such as `map` or
+/// `eqn`.
+pub fn is_section_keyword(value: &LexicalElement) -> bool {
+    use LexicalElement::*;
+
+    matches!(value, Act | Cons | Eqn | Glob | Init | Map | Proc | Sort | Var)
+}
+
+/// Returns whether a token is a comment or a doc comment.
+pub fn is_comment(value: &LexicalElement) -> bool {
+    matches!(value, LexicalElement::Comment(_) | LexicalElement::DocComment(_))
+}
+
+/// Returns the line of the keyword of the section that contains the token at
+/// `index`, or 0 if the token is not inside a section.
+pub fn get_section_start_line(tokens: &[Token], index: usize) -> u32 {
+    tokens[..index].iter()
+        .rev()
+        .find(|token| is_section_keyword(&token.value))
+        .map_or(0, |token| token.loc.get_start_line())
+}
+
+/// Returns the line before which declarations can be inserted for the token at
+/// `index`, which is the start of the section that contains it, except that an
+/// `eqn` section is not separated from the `var` section right before it.
+pub fn get_declaration_insert_line(tokens: &[Token], index: usize) -> u32 {
+    let mut sections = tokens[..index].iter()
+        .rev()
+        .filter(|token| is_section_keyword(&token.value));
+    match (sections.next(), sections.next()) {
+        (Some(eqn), Some(var)) if matches!(
+            (&eqn.value, &var.value),
+            (LexicalElement::Eqn, LexicalElement::Var),
+        ) => var.loc.get_start_line(),
+        (Some(section), _) => section.loc.get_start_line(),
+        (None, _) => 0,
+    }
+}
+
+/// Returns whether two source ranges span exactly the same part of the source.
+pub fn range_equals(lhs: SourceRange, rhs: SourceRange) -> bool {
+    range_contains(lhs, rhs) && range_contains(rhs, lhs)
+}
+
+/// Returns whether `inner` lies completely within `outer`.
+pub fn range_contains(outer: SourceRange, inner: SourceRange) -> bool {
+    let outer_start = (outer.get_start_line(), outer.get_start_char());
+    let outer_end = (outer.get_end_line(), outer.get_end_char());
+    let inner_start = (inner.get_start_line(), inner.get_start_char());
+    let inner_end = (inner.get_end_line(), inner.get_end_char());
+    outer_start <= inner_start && inner_end <= outer_end
+}
+
+/// Returns whether two source ranges share at least one position, where a
+/// range that ends where the other starts also counts.
+pub fn ranges_overlap(lhs: SourceRange, rhs: SourceRange) -> bool {
+    let lhs_start = (lhs.get_start_line(), lhs.get_start_char());
+    let lhs_end = (lhs.get_end_line(), lhs.get_end_char());
+    let rhs_start = (rhs.get_start_line(), rhs.get_start_char());
+    let rhs_end = (rhs.get_end_line(), rhs.get_end_char());
+    lhs_start <= rhs_end && rhs_start <= lhs_end
+}
+
+/// Returns the byte offset in `input` of the given line and character, or
+/// `None` if that position does not exist in `input`.
+pub fn get_byte_offset(input: &str, line: u32, character: u32) -> Option<usize> {
+    let mut offset = 0;
+    for (index, line_str) in input.split('\n').enumerate() {
+        if index == line as usize {
+            return match line_str.char_indices().nth(character as usize) {
+                Some((char_offset, _)) => Some(offset + char_offset),
+                None if line_str.chars().count() == character as usize => {
+                    Some(offset + line_str.len())
+                },
+                None => None,
+            };
+        }
+        offset += line_str.len() + 1;
+    }
+    None
+}
+
+/// Returns the position of the end of `input`.
+pub fn get_end_position(input: &str) -> Position {
+    let line_count = input.split('\n').count() as u32;
+    let last_line_length = input.rsplit('\n').next().map_or(0, |line| line.chars().count());
+    Position::new(line_count - 1, last_line_length as u32)
+}
+
+/// Returns the part of `input` that is spanned by `range`, or an empty string
+/// if the range does not lie within `input`.
+pub fn get_source_slice(input: &str, range: SourceRange) -> &str {
+    let start = get_byte_offset(input, range.get_start_line(), range.get_start_char());
+    let end = get_byte_offset(input, range.get_end_line(), range.get_end_char());
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => &input[start..end],
+        _ => "",
+    }
+}
+
+/// Splits `text` at every occurrence of `separator` that is not nested inside
+/// parentheses, brackets or braces, trimming the resulting parts.
+///
+/// For instance, splitting `Nat # (Nat -> Bool) -> Bool` at `->` results in
+/// `["Nat # (Nat -> Bool)", "Bool"]`.
+pub fn split_at_top_level<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut part_start = 0;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if depth == 0 && rest.starts_with(separator) {
+            result.push(text[part_start..index].trim());
+            index += separator.len();
+            part_start = index;
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        index += c.len_utf8();
+    }
+    result.push(text[part_start..].trim());
+    result
+}
+
+/// Returns the Levenshtein distance between two strings, i.e. the minimal
+/// number of characters that have to be inserted, removed or replaced to turn
+/// `lhs` into `rhs`.
+pub fn get_edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = rhs.chars().collect::<Vec<_>>();
+    let mut previous_row = (0..=rhs.len()).collect::<Vec<_>>();
+    for (i, lhs_char) in lhs.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &rhs_char) in rhs.iter().enumerate() {
+            let replace_cost = previous_row[j] + usize::from(lhs_char != rhs_char);
+            row.push(replace_cost.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[rhs.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_at_top_level_ignores_nested_separators() {
+        assert_eq!(
+            split_at_top_level("Nat # (Nat -> Bool) -> Bool", "->"),
+            vec!["Nat # (Nat -> Bool)", "Bool"],
+        );
+        assert_eq!(
+            split_at_top_level("List(Nat # Nat) # {1, 2}", "#"),
+            vec!["List(Nat # Nat)", "{1, 2}"],
+        );
+        assert_eq!(split_at_top_level(" Nat ", "#"), vec!["Nat"]);
+    }
+
+    #[test]
+    fn edit_distance_counts_insertions_removals_and_replacements() {
+        assert_eq!(get_edit_distance("", ""), 0);
+        assert_eq!(get_edit_distance("count", "count"), 0);
+        assert_eq!(get_edit_distance("", "abc"), 3);
+        assert_eq!(get_edit_distance("abc", ""), 3);
+        assert_eq!(get_edit_distance("kitten", "sitting"), 3);
+        assert_eq!(get_edit_distance("hed", "head"), 1);
+        assert_eq!(get_edit_distance("héad", "head"), 1);
+    }
+
+    #[test]
+    fn ranges_overlap_when_touching() {
+        let range = SourceRange::new(1, 4, 1, 8);
+        assert!(ranges_overlap(range, SourceRange::new(1, 8, 1, 8)));
+        assert!(ranges_overlap(range, SourceRange::new(0, 0, 2, 0)));
+        assert!(!ranges_overlap(range, SourceRange::new(1, 9, 1, 12)));
+    }
+}